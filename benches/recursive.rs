@@ -0,0 +1,76 @@
+//! Recursive grammars built once with `recursive`, against sub-parsers rebuilt on every recursive call.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use par_soeur::{
+  input::LineBasedStr, parse_lexeme, parse_spaces, parse_u32, recursive, Parse, ParseExt, Parser,
+};
+
+/// Nested lists of numbers, such as `[1, [2, 3], []]`, summed up.
+struct Rebuilt;
+
+impl<'a> Parse<LineBasedStr<'a>> for Rebuilt {
+  type Output = u32;
+
+  fn parse(&self, input: LineBasedStr<'a>) -> Parser<u32, LineBasedStr<'a>> {
+    let list = parse_lexeme("[")
+      .left(parse_spaces())
+      .right(
+        Rebuilt
+          .left(parse_spaces())
+          .delimited0(parse_lexeme(",").left(parse_spaces())),
+      )
+      .left(parse_lexeme("]"))
+      .map(|items| items.into_iter().sum());
+
+    parse_u32().or(list).parse(input)
+  }
+}
+
+fn source(depth: usize, width: usize) -> String {
+  if depth == 0 {
+    return "1".to_owned();
+  }
+
+  let item = source(depth - 1, width);
+  format!("[{}]", vec![item.as_str(); width].join(", "))
+}
+
+fn bench_recursive(c: &mut Criterion) {
+  let source = source(5, 4);
+  let expected = Some(4u32.pow(5));
+
+  let mut group = c.benchmark_group("nested lists");
+
+  group.bench_function("rebuilt per call", |b| {
+    b.iter(|| {
+      let sum = Rebuilt.parse(LineBasedStr::from(black_box(source.as_str())));
+      assert_eq!(sum.ok(), expected);
+    })
+  });
+
+  group.bench_function("recursive", |b| {
+    let nested = recursive(|nested| {
+      let list = parse_lexeme("[")
+        .left(parse_spaces())
+        .right(
+          nested
+            .left(parse_spaces())
+            .delimited0(parse_lexeme(",").left(parse_spaces())),
+        )
+        .left(parse_lexeme("]"))
+        .map(|items: Vec<u32>| items.into_iter().sum());
+
+      parse_u32().or(list)
+    });
+
+    b.iter(|| {
+      let sum = nested.parse(LineBasedStr::from(black_box(source.as_str())));
+      assert_eq!(sum.ok(), expected);
+    })
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_recursive);
+criterion_main!(benches);
@@ -1,4 +1,12 @@
-use par_soeur::{input::LineBasedStr, parse_lexeme, Parser, TopParser};
+use par_soeur::{
+  alt,
+  combinator::Recursive,
+  error::{Expected, ParseError},
+  input::{ColumnBased, Input, LineBasedStr, Partial, StrBased},
+  parse_lexeme, parse_spaces, parse_take, parse_while,
+  read::{ReadBuffer, Reader},
+  recursive, ParseExt, Parser,
+};
 use std::{collections::HashMap, io::stdin};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -11,124 +19,161 @@ enum Value {
   Null,
 }
 
-fn value_parser<'a>(
-) -> TopParser<'a, fn(LineBasedStr<'a>) -> Parser<Value, LineBasedStr<'a>>, Value, LineBasedStr<'a>>
-{
-  TopParser::from_input_parser(value_parser_fn)
+/// Values are read from a stream, so the input may be cut anywhere.
+type JsonInput<'a> = Partial<LineBasedStr<'a>>;
+
+fn advance(input: JsonInput, count: usize) -> JsonInput {
+  let col = input.col() + input.col_width(&input.as_str()[..count]);
+  input.set_col(col).advance(count)
 }
 
-fn value_parser_fn(input: LineBasedStr) -> Parser<Value, LineBasedStr> {
-  let string_parser = || {
-    TopParser::from_input_parser(|input: LineBasedStr| {
-      let s = input.as_str();
+/// JSON string.
+fn string(input: JsonInput) -> Parser<String, JsonInput> {
+  let s = input.as_str();
+  let mut chars = s.char_indices();
 
-      if s.is_empty() {
-        return Parser::NoParse;
-      }
+  if s.is_empty() {
+    return Parser::end_of_input(input, Expected::named("string"), 1);
+  }
+
+  if chars.next().map(|(_, c)| c) != Some('"') {
+    return Parser::NoParse {
+      error: ParseError::new(input, Expected::named("string")),
+    };
+  }
+
+  let mut data = String::new();
 
-      let mut chars = s.chars();
-      if chars.next() != Some('"') {
-        return Parser::NoParse;
+  loop {
+    let (i, c) = match chars.next() {
+      Some(ic) => ic,
+      None => {
+        let count = s.len();
+        let input = advance(input, count);
+        return Parser::end_of_input(input, Expected::lexeme("\""), 1);
       }
+    };
 
-      loop {
-        let c = chars.next();
-
-        match c {
-          Some('\') => {
-            match chars.next() {
-              Some('u') => {
-                let a = chars.next();
-                let b = chars.next();
-                let c = chars.next();
-                let d = chars.next();
-
-                match (a, b, c, d) {
-                  (Some(a), Some(b), Some(c), Some(d)) if a.is_ascii_hexdigit() && b.is_ascii_hexdigit() && c.is_ascii_hexdigit() && d.is_ascii_hexdigit() => {
-                    let o = vec![
-                      u32::from_str_radix(a, radix)
-                  }
-                }
-              }
-            }
-          },
-        }
+    match c {
+      '"' => {
+        let count = i + 1;
+        let input = advance(input, count);
+        return Parser::Parsed { data, input };
       }
 
-      // let input = &input[1 + s.len()..];
+      '\\' => {
+        let escaped = match chars.next().map(|(_, c)| c) {
+          Some('"') => Some('"'),
+          Some('\\') => Some('\\'),
+          Some('/') => Some('/'),
+          Some('b') => Some('\u{8}'),
+          Some('f') => Some('\u{c}'),
+          Some('n') => Some('\n'),
+          Some('r') => Some('\r'),
+          Some('t') => Some('\t'),
+          Some('u') => {
+            let hex = s.get(i + 2..i + 6).unwrap_or("");
+            let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+
+            if c.is_some() {
+              chars.nth(3);
+            }
 
-      if input.is_empty() {
-        return Parser::NoParse;
+            c
+          }
+          _ => None,
+        };
+
+        match escaped {
+          Some(c) => data.push(c),
+
+          // the escape sequence might be cut by the end of the input
+          None if s.len() < i + 6 && input.is_partial() => {
+            let needed = i + 6 - s.len();
+            let input = advance(input, i + 1);
+            return Parser::end_of_input(input, Expected::named("escape sequence"), needed);
+          }
+
+          None => {
+            let count = i + 1;
+            let input = advance(input, count);
+            return Parser::NoParse {
+              error: ParseError::new(input, Expected::named("escape sequence")),
+            };
+          }
+        }
       }
 
-      let input = &input[1..];
-      Parser::Parsed { data, input }
-    })
-  };
-
-  let number_parser = TopParser::from_input_parser(|input: &str| {
-    let m = input
-      .chars()
-      .take_while(|&c| c.is_ascii_digit() || "-.".contains(c));
-    let m = &input[..m.count()];
-    match lexical_core::parse::<f64>(m.as_bytes())
-      .ok()
-      .map(Value::Number)
-    {
-      Some(data) => Parser::Parsed {
-        data,
-        input: &input[m.len()..],
-      },
-      None => Parser::NoParse,
+      c => data.push(c),
     }
-  });
-
-  let bool_parser = {
-    parse_lexeme("true")
-      .const_map(true)
-      .or(parse_lexeme("false").const_map(false))
-      .map(Value::Bool)
-  };
-
-  let null_parser = parse_lexeme("null").const_map(Value::Null);
-
-  let ws_parser = || par_soeur::parse_while(char::is_whitespace).opt();
-
-  let array_el_parser = value_parser()
-    .delimited0(parse_lexeme(",").left(ws_parser()))
-    .left(parse_lexeme(",").left(ws_parser()).opt());
-  let array_parser = parse_lexeme("[")
-    .left(ws_parser())
-    .right(array_el_parser)
-    .left(parse_lexeme("]"))
-    .map(Value::Array);
-
-  let obj_pair_parser = string_parser()
-    .left(ws_parser())
-    .left(parse_lexeme(":"))
-    .left(ws_parser())
-    .zip(value_parser(), |k, v| (k, v));
-  let obj_parser = parse_lexeme("{")
-    .left(ws_parser())
-    .right(obj_pair_parser.delimited0(parse_lexeme(",").left(ws_parser())))
-    .left(ws_parser())
-    .left(parse_lexeme("}"))
-    .map(|kvs| Value::Object(kvs.into_iter().collect()));
-
-  string_parser()
-    .map(Value::String)
-    .or(number_parser)
-    .or(bool_parser)
-    .or(null_parser)
-    .or(array_parser)
-    .or(obj_parser)
-    .parse(input)
+  }
+}
+
+/// Parser of JSON values, which arrays and objects contain in turn.
+///
+/// The grammar is built once, for inputs lent by the reader for as long as its buffer lives.
+fn value_parser<'a>() -> Recursive<'a, Value, JsonInput<'a>> {
+  recursive(|value| {
+    let digits = || parse_while(|c| c.is_ascii_digit());
+    let exponent = (parse_lexeme("e") | parse_lexeme("E"))
+      >> (parse_lexeme("+") | parse_lexeme("-")).opt()
+      >> digits();
+    let number = (parse_lexeme("-").opt()
+      >> digits()
+      >> (parse_lexeme(".") >> digits()).opt()
+      >> exponent.opt())
+    .recognize()
+    .map(|s| Value::Number(lexical_core::parse(s.as_bytes()).expect("number syntax")))
+    .label("number");
+
+    let bool = parse_lexeme("true").const_map(true) | parse_lexeme("false").const_map(false);
+    let null = parse_lexeme("null").const_map(Value::Null);
+
+    // comma between the elements of arrays and the members of objects
+    let comma = || parse_lexeme(",") << parse_spaces();
+
+    let els = (value.clone() << parse_spaces()).delimited0(comma());
+    let array = (parse_lexeme("[") << parse_spaces() >> (els << parse_lexeme("]")).cut())
+      .map(Value::Array)
+      .context("array");
+
+    let member = (string.left(parse_spaces()) << parse_lexeme(":").cut() << parse_spaces())
+      + (value << parse_spaces()).cut();
+    let members = member.label("object member").delimited0(comma());
+    let object = (parse_lexeme("{") << parse_spaces() >> (members << parse_lexeme("}")).cut())
+      .map(|kvs| Value::Object(kvs.into_iter().collect()))
+      .context("object");
+
+    alt((
+      string.map(Value::String),
+      number,
+      bool.map(Value::Bool),
+      null,
+      array,
+      object,
+    ))
+  })
 }
 
 fn main() {
-  let mut line = String::new();
-  while let Ok(_) = stdin().read_line(&mut line) {
-    println!("{:?}", value_parser().parse(&line));
-    line.clear();
+  // text read is kept for as long as the buffer lives, so that the grammar is built once for it
+  let buffer = ReadBuffer::new();
+
+  // whitespace after a value is left for the next one, so that values are handed out as soon as they
+  // end, rather than once what follows them has been read
+  let end = (!parse_take(1)).label("end of input");
+  let next_value = parse_spaces() >> (value_parser().map(Some) | end.const_map(None));
+
+  let mut reader = Reader::new(stdin().lock()).lend(&buffer);
+
+  loop {
+    match reader.parse(&next_value) {
+      Ok(Some(Some(value))) => println!("{:?}", value),
+      Ok(Some(None)) | Ok(None) => break,
+      Err(error) => {
+        println!("error: {}", error);
+        break;
+      }
+    }
   }
 }
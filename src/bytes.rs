@@ -0,0 +1,338 @@
+use crate::{
+  error::{Expected, ParseError},
+  input::{BitBased, ByteBased, ByteSliceBased},
+  Parse, Parser, TopParser,
+};
+use std::{convert::TryFrom, mem};
+
+/// Top parser yielding a slice of the input.
+type SliceParser<'a, F, I> = TopParser<'a, F, &'a [u8], I>;
+
+/// Take a given number of bytes off an input.
+fn take<'a, I>(input: I, count: usize) -> Parser<&'a [u8], I>
+where
+  I: ByteSliceBased<'a>,
+{
+  let bytes = input.as_byte_slice();
+
+  match bytes.get(..count) {
+    Some(data) => Parser::Parsed {
+      data,
+      input: input.advance(count),
+    },
+
+    None => {
+      let needed = count - bytes.len();
+      Parser::end_of_input(input, Expected::named(format!("{} bytes", count)), needed)
+    }
+  }
+}
+
+pub fn parse_tag<'a, I>(
+  tag: impl 'a + AsRef<[u8]>,
+) -> TopParser<'a, impl Fn(I) -> Parser<(), I>, (), I>
+where
+  I: 'a + ByteBased,
+{
+  TopParser::from_input_parser(move |input: I| {
+    let tag = tag.as_ref();
+
+    if input.as_bytes().starts_with(tag) {
+      let input = input.advance(tag.len());
+      Parser::Parsed { data: (), input }
+    } else if input.is_partial() && tag.starts_with(input.as_bytes()) {
+      let needed = tag.len() - input.as_bytes().len();
+      Parser::end_of_input(
+        input,
+        Expected::lexeme(tag.escape_ascii().to_string()),
+        needed,
+      )
+    } else {
+      Parser::NoParse {
+        error: ParseError::new(input, Expected::lexeme(tag.escape_ascii().to_string())),
+      }
+    }
+  })
+}
+
+pub fn parse_take<'a, I>(count: usize) -> SliceParser<'a, impl Fn(I) -> Parser<&'a [u8], I>, I>
+where
+  I: 'a + ByteSliceBased<'a>,
+{
+  TopParser::from_input_parser(move |input: I| take(input, count))
+}
+
+/// Parse a block of bytes prefixed by its length.
+///
+/// The length is parsed by `length`, typically one of the integer parsers of this module.
+pub fn parse_length_prefixed<'a, N, I>(
+  length: impl 'a + Parse<I, Output = N>,
+) -> SliceParser<'a, impl Fn(I) -> Parser<&'a [u8], I>, I>
+where
+  I: 'a + ByteSliceBased<'a>,
+  usize: TryFrom<N>,
+{
+  TopParser::from_input_parser(move |input: I| {
+    length.parse(input).and_then(|data, input| {
+      // a length that doesn’t fit in memory cannot fit in the input either
+      let count = usize::try_from(data).unwrap_or(usize::MAX);
+      take(input, count)
+    })
+  })
+}
+
+/// Parse up to 64 bits, most significant first, into the low bits of a `u64`.
+///
+/// # Panics
+///
+/// Panics if `count` is greater than 64.
+pub fn parse_bits<'a, I>(count: usize) -> TopParser<'a, impl Fn(I) -> Parser<u64, I>, u64, I>
+where
+  I: 'a + BitBased,
+{
+  assert!(count <= 64, "cannot parse more than 64 bits at once");
+
+  TopParser::from_input_parser(move |input: I| {
+    let (bytes, bit) = input.as_bits();
+
+    let available = bytes.len() * 8 - bit;
+
+    if available < count {
+      let needed = (count - available).div_ceil(8);
+      return Parser::end_of_input(input, Expected::named(format!("{} bits", count)), needed);
+    }
+
+    let data = (bit..bit + count).fold(0, |data, i| {
+      data << 1 | u64::from(bytes[i / 8] >> (7 - i % 8) & 1)
+    });
+    let input = input.advance_bits(count);
+
+    Parser::Parsed { data, input }
+  })
+}
+
+macro_rules! impl_parse_int {
+  ($($name:ident, $ty:ident, $from_bytes:ident, $doc:literal;)*) => {
+    $(
+      #[doc = $doc]
+      pub fn $name<'a, I>() -> TopParser<'a, impl Fn(I) -> Parser<$ty, I>, $ty, I>
+      where
+        I: 'a + ByteBased,
+      {
+        TopParser::from_input_parser(|input: I| {
+          const SIZE: usize = mem::size_of::<$ty>();
+
+          match input.as_bytes().get(..SIZE) {
+            Some(bytes) => {
+              let data = $ty::$from_bytes(<[u8; SIZE]>::try_from(bytes).unwrap());
+              let input = input.advance(SIZE);
+
+              Parser::Parsed { data, input }
+            }
+
+            None => {
+              let needed = SIZE - input.as_bytes().len();
+              Parser::end_of_input(input, Expected::named(stringify!($ty)), needed)
+            }
+          }
+        })
+      }
+    )*
+  };
+}
+
+impl_parse_int! {
+  parse_u8, u8, from_be_bytes, "Parse a `u8`.";
+  parse_i8, i8, from_be_bytes, "Parse an `i8`.";
+  parse_u16_be, u16, from_be_bytes, "Parse a big-endian `u16`.";
+  parse_u16_le, u16, from_le_bytes, "Parse a little-endian `u16`.";
+  parse_i16_be, i16, from_be_bytes, "Parse a big-endian `i16`.";
+  parse_i16_le, i16, from_le_bytes, "Parse a little-endian `i16`.";
+  parse_u32_be, u32, from_be_bytes, "Parse a big-endian `u32`.";
+  parse_u32_le, u32, from_le_bytes, "Parse a little-endian `u32`.";
+  parse_i32_be, i32, from_be_bytes, "Parse a big-endian `i32`.";
+  parse_i32_le, i32, from_le_bytes, "Parse a little-endian `i32`.";
+  parse_u64_be, u64, from_be_bytes, "Parse a big-endian `u64`.";
+  parse_u64_le, u64, from_le_bytes, "Parse a little-endian `u64`.";
+  parse_i64_be, i64, from_be_bytes, "Parse a big-endian `i64`.";
+  parse_i64_le, i64, from_le_bytes, "Parse a little-endian `i64`.";
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    error::Found,
+    input::{BitBasedBytes, Partial},
+    ParseExt,
+  };
+
+  #[test]
+  fn parse_int_test() {
+    let input: &[u8] = &[0x12, 0x34, 0x56, 0x78, 0x9a];
+
+    assert_eq!(parse_u8().parse(input).ok(), Some(0x12));
+    assert_eq!(parse_i8().parse(&[0xff][..]).ok(), Some(-1));
+    assert_eq!(parse_u16_be().parse(input).ok(), Some(0x1234));
+    assert_eq!(parse_u16_le().parse(input).ok(), Some(0x3412));
+    assert_eq!(parse_u32_be().parse(input).ok(), Some(0x12345678));
+    assert_eq!(parse_u32_le().parse(input).ok(), Some(0x78563412));
+    assert_eq!(parse_i16_be().parse(&[0xff, 0xfe][..]).ok(), Some(-2));
+    assert_eq!(parse_i16_le().parse(&[0xfe, 0xff][..]).ok(), Some(-2));
+    assert_eq!(
+      parse_u32_be().right(parse_u8()).parse(input),
+      Parser::Parsed {
+        data: 0x9a,
+        input: &[][..]
+      }
+    );
+
+    let error = parse_u64_be().parse(input).result().unwrap_err();
+    assert_eq!(error.expected, vec![Expected::named("u64")]);
+    assert_eq!(error.found, Found::EndOfInput);
+    assert_eq!(error.input, input);
+  }
+
+  #[test]
+  fn parse_tag_test() {
+    let input: &[u8] = b"\x89PNG\r\n\x1a\nrest";
+
+    assert_eq!(
+      parse_tag(b"\x89PNG\r\n\x1a\n").parse(input),
+      Parser::Parsed {
+        data: (),
+        input: &b"rest"[..]
+      }
+    );
+
+    let error = parse_tag(b"GIF8")
+      .or(parse_tag(b"\xff\xd8"))
+      .parse(input)
+      .result()
+      .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "expected one of: `GIF8`, `\\xff\\xd8`; found byte 0x89"
+    );
+  }
+
+  #[test]
+  fn parse_take_test() {
+    let input: &[u8] = b"abcdef";
+
+    assert_eq!(
+      parse_take(4).parse(input),
+      Parser::Parsed {
+        data: &b"abcd"[..],
+        input: &b"ef"[..]
+      }
+    );
+
+    let error = parse_take(7).parse(input).result().unwrap_err();
+    assert_eq!(error.to_string(), "expected 7 bytes, found end of input");
+  }
+
+  #[test]
+  fn parse_length_prefixed_test() {
+    let frames = parse_length_prefixed(parse_u16_le()).many0();
+    let input: &[u8] = &[2, 0, b'h', b'i', 0, 0, 3, 0, b'y', b'o'];
+
+    assert_eq!(
+      frames.parse(input),
+      Parser::Parsed {
+        data: vec![&b"hi"[..], &b""[..]],
+        input: &[3, 0, b'y', b'o'][..]
+      }
+    );
+
+    let error = parse_length_prefixed(parse_u8())
+      .parse(&[3, b'y', b'o'][..])
+      .result()
+      .unwrap_err();
+    assert_eq!(error.to_string(), "expected 3 bytes, found end of input");
+    assert_eq!(error.input, &b"yo"[..]);
+  }
+
+  #[test]
+  fn parse_bits_test() {
+    // a 4-bit version, a 12-bit length and a flag, then a byte-aligned payload
+    let header = parse_bits(4)
+      .zip(parse_bits(12), |version, len| (version, len))
+      .zip(parse_bits(1), |(version, len), flag| {
+        (version, len, flag == 1)
+      })
+      .zip(parse_u8(), |header, payload| (header, payload));
+    let input = BitBasedBytes::new(&[0x41, 0x23, 0x80, 0x7f, 0x00]);
+
+    assert_eq!(
+      header.parse(input),
+      Parser::Parsed {
+        data: ((4, 0x123, true), 0x7f),
+        input: BitBasedBytes {
+          input: &[0x00],
+          offset: 4,
+          bit: 0
+        }
+      }
+    );
+
+    assert_eq!(
+      parse_bits(64).parse(BitBasedBytes::new(&[0xff; 8])).ok(),
+      Some(u64::MAX)
+    );
+
+    let error = parse_bits(3)
+      .right(parse_bits(6))
+      .parse(BitBasedBytes::new(&[0xff]))
+      .result()
+      .unwrap_err();
+    assert_eq!(error.to_string(), "expected 6 bits, found end of input");
+    assert_eq!(error.input.bit, 3);
+  }
+
+  #[test]
+  fn bit_based_merge_test() {
+    let input = BitBasedBytes::new(&[0b1010_0000]);
+    let error = parse_bits(2)
+      .right(parse_tag(b"x"))
+      .or(parse_bits(1).right(parse_tag(b"y")))
+      .parse(input)
+      .result()
+      .unwrap_err();
+
+    // the first alternative made it further into the partially read byte
+    assert_eq!(error.expected, vec![Expected::lexeme("x")]);
+    assert_eq!(error.input.bit, 2);
+    assert_eq!(error.offset(), 0);
+  }
+
+  #[test]
+  fn partial_test() {
+    let frame = parse_tag(b"FR").right(parse_length_prefixed(parse_u16_be()));
+
+    assert!(matches!(
+      frame.parse(Partial::new(&b"F"[..])),
+      Parser::Incomplete { needed: 1, .. }
+    ));
+    assert!(matches!(
+      frame.parse(Partial::new(&b"FR\x00"[..])),
+      Parser::Incomplete { needed: 1, .. }
+    ));
+    assert!(matches!(
+      frame.parse(Partial::new(&b"FR\x00\x05abc"[..])),
+      Parser::Incomplete { needed: 2, .. }
+    ));
+    assert!(matches!(
+      frame.parse(Partial::new(&b"FR\x00\x05abc"[..]).complete()),
+      Parser::NoParse { .. }
+    ));
+    assert_eq!(
+      frame.parse(Partial::new(&b"FR\x00\x03abc"[..])).ok(),
+      Some(&b"abc"[..])
+    );
+    assert!(matches!(
+      parse_bits(12).parse(Partial::new(BitBasedBytes::new(&[0xff]))),
+      Parser::Incomplete { needed: 1, .. }
+    ));
+  }
+}
@@ -0,0 +1,1001 @@
+use crate::{
+  error::{Expected, ParseError},
+  input::{self, Input, PositionBased, RecoveryBased, SliceBased, Span},
+  Parse, Parser, TopParser,
+};
+use std::{
+  cell::OnceCell,
+  marker::PhantomData,
+  ops::{self, Add, BitOr, Shl, Shr},
+  rc::{Rc, Weak},
+};
+
+/// Parser combining the values of two parsers in sequence; see [`ParseExt::zip`].
+///
+/// [`ParseExt::zip`]: crate::ParseExt::zip
+#[derive(Clone, Debug)]
+pub struct Zip<P, Q, F> {
+  pub(crate) first: P,
+  pub(crate) second: Q,
+  pub(crate) f: F,
+}
+
+impl<P, Q, F, C, I> Parse<I> for Zip<P, Q, F>
+where
+  P: Parse<I>,
+  Q: Parse<I>,
+  F: Fn(P::Output, Q::Output) -> C,
+{
+  type Output = C;
+
+  fn parse(&self, input: I) -> Parser<C, I> {
+    self
+      .first
+      .parse(input)
+      .and_then(|data, input| self.second.parse(input).map(|data2| (self.f)(data, data2)))
+  }
+}
+
+/// Parser keeping the value of the first of two parsers in sequence; see [`ParseExt::left`].
+///
+/// [`ParseExt::left`]: crate::ParseExt::left
+#[derive(Clone, Debug)]
+pub struct Left<P, Q> {
+  pub(crate) first: P,
+  pub(crate) second: Q,
+}
+
+impl<P, Q, I> Parse<I> for Left<P, Q>
+where
+  P: Parse<I>,
+  Q: Parse<I>,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    self
+      .first
+      .parse(input)
+      .and_then(|data, input| self.second.parse(input).map(|_| data))
+  }
+}
+
+/// Parser keeping the value of the second of two parsers in sequence; see [`ParseExt::right`].
+///
+/// [`ParseExt::right`]: crate::ParseExt::right
+#[derive(Clone, Debug)]
+pub struct Right<P, Q> {
+  pub(crate) first: P,
+  pub(crate) second: Q,
+}
+
+impl<P, Q, I> Parse<I> for Right<P, Q>
+where
+  P: Parse<I>,
+  Q: Parse<I>,
+{
+  type Output = Q::Output;
+
+  fn parse(&self, input: I) -> Parser<Q::Output, I> {
+    self
+      .first
+      .parse(input)
+      .and_then(|_, input| self.second.parse(input))
+  }
+}
+
+/// Parser choosing what to parse next from a value; see [`ParseExt::and_then`].
+///
+/// [`ParseExt::and_then`]: crate::ParseExt::and_then
+#[derive(Clone, Debug)]
+pub struct AndThen<P, F> {
+  pub(crate) parser: P,
+  pub(crate) f: F,
+}
+
+impl<P, F, Q, I> Parse<I> for AndThen<P, F>
+where
+  P: Parse<I>,
+  F: Fn(P::Output) -> Q,
+  Q: Parse<I>,
+{
+  type Output = Q::Output;
+
+  fn parse(&self, input: I) -> Parser<Q::Output, I> {
+    self
+      .parser
+      .parse(input)
+      .and_then(|data, input| (self.f)(data).parse(input))
+  }
+}
+
+/// Parser mapping the value of a parser; see [`ParseExt::map`].
+///
+/// [`ParseExt::map`]: crate::ParseExt::map
+#[derive(Clone, Debug)]
+pub struct Map<P, F> {
+  pub(crate) parser: P,
+  pub(crate) f: F,
+}
+
+impl<P, F, B, I> Parse<I> for Map<P, F>
+where
+  P: Parse<I>,
+  F: Fn(P::Output) -> B,
+{
+  type Output = B;
+
+  fn parse(&self, input: I) -> Parser<B, I> {
+    self.parser.parse(input).map(|data| (self.f)(data))
+  }
+}
+
+/// Parser replacing the value of a parser by a constant; see [`ParseExt::const_map`].
+///
+/// [`ParseExt::const_map`]: crate::ParseExt::const_map
+#[derive(Clone, Debug)]
+pub struct ConstMap<P, B> {
+  pub(crate) parser: P,
+  pub(crate) value: B,
+}
+
+impl<P, B, I> Parse<I> for ConstMap<P, B>
+where
+  P: Parse<I>,
+  B: Clone,
+{
+  type Output = B;
+
+  fn parse(&self, input: I) -> Parser<B, I> {
+    self.parser.parse(input).map(|_| self.value.clone())
+  }
+}
+
+/// Parser applying a parser zero or more times; see [`ParseExt::many0`].
+///
+/// [`ParseExt::many0`]: crate::ParseExt::many0
+#[derive(Clone, Debug)]
+pub struct Many0<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for Many0<P>
+where
+  P: Parse<I>,
+  I: Clone + PartialEq,
+{
+  type Output = Vec<P::Output>;
+
+  fn parse(&self, mut i: I) -> Parser<Vec<P::Output>, I> {
+    let mut results = Vec::new();
+
+    loop {
+      match self.parser.parse(i.clone()) {
+        Parser::Parsed { data, input } => {
+          if input == i {
+            // input hasn’t changed, which might indicate that the parser didn’t consume; break
+            break;
+          }
+
+          results.push(data);
+          i = input;
+        }
+
+        Parser::NoParse { .. } => break,
+        failure => return failure.into_failure(),
+      }
+    }
+
+    Parser::Parsed {
+      input: i,
+      data: results,
+    }
+  }
+}
+
+/// Parser applying a parser one or more times; see [`ParseExt::many1`].
+///
+/// [`ParseExt::many1`]: crate::ParseExt::many1
+#[derive(Clone, Debug)]
+pub struct Many1<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for Many1<P>
+where
+  P: Parse<I>,
+  I: Clone + Input,
+{
+  type Output = Vec<P::Output>;
+
+  fn parse(&self, mut i: I) -> Parser<Vec<P::Output>, I> {
+    let mut results = Vec::new();
+
+    loop {
+      match self.parser.parse(i.clone()) {
+        Parser::Parsed { data, input } => {
+          // input hasn’t changed, which might indicate that the parser didn’t consume; break
+          if input == i {
+            if results.is_empty() {
+              return Parser::NoParse {
+                error: ParseError::new(i, Expected::named("non-empty match")),
+              };
+            }
+
+            break;
+          }
+
+          results.push(data);
+          i = input;
+        }
+
+        Parser::NoParse { error } => {
+          if results.is_empty() {
+            return Parser::NoParse { error };
+          }
+
+          break;
+        }
+
+        failure => return failure.into_failure(),
+      }
+    }
+
+    Parser::Parsed {
+      input: i,
+      data: results,
+    }
+  }
+}
+
+/// Parser making a parser optional; see [`ParseExt::opt`].
+///
+/// [`ParseExt::opt`]: crate::ParseExt::opt
+#[derive(Clone, Debug)]
+pub struct Opt<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for Opt<P>
+where
+  P: Parse<I>,
+  I: Clone,
+{
+  type Output = Option<P::Output>;
+
+  fn parse(&self, input: I) -> Parser<Option<P::Output>, I> {
+    match self.parser.parse(input.clone()) {
+      Parser::Parsed { data, input } => Parser::Parsed {
+        data: Some(data),
+        input,
+      },
+      Parser::NoParse { .. } => Parser::Parsed { data: None, input },
+      failure => failure.into_failure(),
+    }
+  }
+}
+
+/// Parser committing to a parser; see [`ParseExt::cut`].
+///
+/// [`ParseExt::cut`]: crate::ParseExt::cut
+#[derive(Clone, Debug)]
+pub struct Cut<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for Cut<P>
+where
+  P: Parse<I>,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    match self.parser.parse(input) {
+      Parser::NoParse { error } => Parser::Fatal { error },
+      p => p,
+    }
+  }
+}
+
+/// Parser trying a parser, then another one if the first one fails; see [`ParseExt::or`].
+///
+/// [`ParseExt::or`]: crate::ParseExt::or
+#[derive(Clone, Debug)]
+pub struct Or<P, Q> {
+  pub(crate) first: P,
+  pub(crate) second: Q,
+}
+
+impl<P, Q, I> Parse<I> for Or<P, Q>
+where
+  P: Parse<I>,
+  Q: Parse<I, Output = P::Output>,
+  I: Clone + Input,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    match self.first.parse(input.clone()) {
+      Parser::NoParse { error } => match self.second.parse(input) {
+        Parser::NoParse { error: error2 } => Parser::NoParse {
+          error: error.merge(error2),
+        },
+        p => p,
+      },
+      p => p,
+    }
+  }
+}
+
+/// Parser succeeding, without consuming anything, only where a parser fails; see
+/// [`ParseExt::not_ahead`].
+///
+/// [`ParseExt::not_ahead`]: crate::ParseExt::not_ahead
+#[derive(Clone, Debug)]
+pub struct NotAhead<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for NotAhead<P>
+where
+  P: Parse<I>,
+  I: Clone + Input,
+{
+  type Output = ();
+
+  fn parse(&self, input: I) -> Parser<(), I> {
+    match self.parser.parse(input.clone()) {
+      Parser::Parsed { .. } => Parser::NoParse {
+        error: ParseError::new(input, Expected::named("something else")),
+      },
+      Parser::NoParse { .. } => Parser::Parsed { data: (), input },
+      failure => failure.into_failure(),
+    }
+  }
+}
+
+/// Parser wrapping the value of a parser along with its span; see [`ParseExt::spanned`].
+///
+/// [`ParseExt::spanned`]: crate::ParseExt::spanned
+#[derive(Clone, Debug)]
+pub struct SpannedParser<P> {
+  pub(crate) parser: P,
+}
+
+impl<P, I> Parse<I> for SpannedParser<P>
+where
+  P: Parse<I>,
+  I: PositionBased,
+{
+  type Output = input::Spanned<P::Output>;
+
+  fn parse(&self, input: I) -> Parser<input::Spanned<P::Output>, I> {
+    let start = input.position();
+    let file = input.file();
+
+    self.parser.parse(input).and_then(|data, input| {
+      // parsers consuming nothing end before they start on inputs skipping over things
+      let end = input.end_position().max(start);
+
+      Parser::Parsed {
+        data: input::Spanned {
+          value: data,
+          span: Span {
+            file,
+            ..Span::new(start, end)
+          },
+        },
+        input,
+      }
+    })
+  }
+}
+
+/// Parser returning the slice of input a parser consumed; see [`ParseExt::recognize`].
+///
+/// [`ParseExt::recognize`]: crate::ParseExt::recognize
+#[derive(Clone, Debug)]
+pub struct Recognize<'a, P> {
+  pub(crate) parser: P,
+  pub(crate) _phantom: PhantomData<&'a str>,
+}
+
+impl<'a, P, I> Parse<I> for Recognize<'a, P>
+where
+  P: Parse<I>,
+  I: SliceBased<'a>,
+{
+  type Output = &'a str;
+
+  fn parse(&self, input: I) -> Parser<&'a str, I> {
+    let s = input.as_slice();
+
+    self
+      .parser
+      .parse(input)
+      .and_then(|_, input| Parser::Parsed {
+        data: &s[..s.len() - input.as_str().len()],
+        input,
+      })
+  }
+}
+
+/// Parser naming a parser; see [`ParseExt::label`].
+///
+/// [`ParseExt::label`]: crate::ParseExt::label
+#[derive(Clone, Debug)]
+pub struct Label<P> {
+  pub(crate) parser: P,
+  pub(crate) label: String,
+}
+
+impl<P, I> Parse<I> for Label<P>
+where
+  P: Parse<I>,
+  I: Clone + PartialEq,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    self.parser.parse(input.clone()).map_error(|mut error| {
+      if error.input == input {
+        error.expected = vec![Expected::named(self.label.clone())];
+      } else {
+        error.context.push(self.label.clone());
+      }
+
+      error
+    })
+  }
+}
+
+/// Parser adding a context to the errors of a parser; see [`ParseExt::context`].
+///
+/// [`ParseExt::context`]: crate::ParseExt::context
+#[derive(Clone, Debug)]
+pub struct Context<P> {
+  pub(crate) parser: P,
+  pub(crate) context: String,
+}
+
+impl<P, I> Parse<I> for Context<P>
+where
+  P: Parse<I>,
+  I: Clone + PartialEq,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    self.parser.parse(input.clone()).map_error(|mut error| {
+      // failing right away means the parser wasn’t entered, which alternatives tried by `or` rely on
+      if error.input != input {
+        error.context.push(self.context.clone());
+      }
+
+      error
+    })
+  }
+}
+
+/// Parser recovering from the failures of a parser; see [`ParseExt::recover_with`].
+///
+/// [`ParseExt::recover_with`]: crate::ParseExt::recover_with
+#[derive(Clone, Debug)]
+pub struct RecoverWith<P, S, F> {
+  pub(crate) parser: P,
+  pub(crate) sync: S,
+  pub(crate) placeholder: F,
+}
+
+impl<P, S, F, I> Parse<I> for RecoverWith<P, S, F>
+where
+  P: Parse<I>,
+  S: Parse<I>,
+  F: Fn(&ParseError<I>) -> P::Output,
+  I: Clone + RecoveryBased,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    match self.parser.parse(input) {
+      Parser::NoParse { error } => {
+        let data = (self.placeholder)(&error);
+        let mut i = error.input.clone().record_error(error);
+
+        while let Parser::NoParse { .. } | Parser::Fatal { .. } = self.sync.parse(i.clone()) {
+          match i.clone().skip() {
+            Some(next) => i = next,
+            None => break,
+          }
+        }
+
+        Parser::Parsed { data, input: i }
+      }
+      p => p,
+    }
+  }
+}
+
+/// Parser applying a parser zero or more times, separated by a delimiter; see
+/// [`ParseExt::delimited0`].
+///
+/// [`ParseExt::delimited0`]: crate::ParseExt::delimited0
+#[derive(Clone, Debug)]
+pub struct Delimited0<P, D> {
+  pub(crate) parser: P,
+  pub(crate) delimiter: D,
+}
+
+impl<P, D, I> Parse<I> for Delimited0<P, D>
+where
+  P: Parse<I>,
+  D: Parse<I>,
+  I: Clone,
+{
+  type Output = Vec<P::Output>;
+
+  fn parse(&self, mut i: I) -> Parser<Vec<P::Output>, I> {
+    let mut even = true;
+    let mut results = Vec::new();
+
+    let error = loop {
+      if even {
+        match self.parser.parse(i.clone()) {
+          Parser::Parsed { data, input } => {
+            results.push(data);
+            i = input;
+          }
+          Parser::NoParse { error } => break error,
+          failure => return failure.into_failure(),
+        }
+      } else {
+        match self.delimiter.parse(i.clone()) {
+          Parser::Parsed { input, .. } => i = input,
+          Parser::NoParse { error } => break error,
+          failure => return failure.into_failure(),
+        }
+      }
+
+      even = !even;
+    };
+
+    if !even || results.is_empty() {
+      Parser::Parsed {
+        data: results,
+        input: i,
+      }
+    } else {
+      // a delimiter was parsed but not followed by an element
+      Parser::NoParse { error }
+    }
+  }
+}
+
+/// Parser applying a parser one or more times, separated by a delimiter; see
+/// [`ParseExt::delimited1`].
+///
+/// [`ParseExt::delimited1`]: crate::ParseExt::delimited1
+#[derive(Clone, Debug)]
+pub struct Delimited1<P, D> {
+  pub(crate) parser: P,
+  pub(crate) delimiter: D,
+}
+
+impl<P, D, I> Parse<I> for Delimited1<P, D>
+where
+  P: Parse<I>,
+  D: Parse<I>,
+  I: Clone,
+{
+  type Output = Vec<P::Output>;
+
+  fn parse(&self, mut i: I) -> Parser<Vec<P::Output>, I> {
+    let mut even = true;
+    let mut results = Vec::new();
+
+    let error = loop {
+      if even {
+        match self.parser.parse(i.clone()) {
+          Parser::Parsed { data, input } => {
+            results.push(data);
+            i = input;
+          }
+          Parser::NoParse { error } => break error,
+          failure => return failure.into_failure(),
+        }
+      } else {
+        match self.delimiter.parse(i.clone()) {
+          Parser::Parsed { input, .. } => i = input,
+          Parser::NoParse { error } => break error,
+          failure => return failure.into_failure(),
+        }
+      }
+
+      even = !even;
+    };
+
+    if even {
+      // either no element at all, or a delimiter not followed by an element
+      Parser::NoParse { error }
+    } else {
+      Parser::Parsed {
+        data: results,
+        input: i,
+      }
+    }
+  }
+}
+
+/// Parser trying parsers in order, until one of them doesn’t fail; see [`choice`].
+///
+/// [`choice`]: crate::choice
+#[derive(Clone, Debug)]
+pub struct Choice<P> {
+  pub(crate) parsers: Vec<P>,
+}
+
+impl<P, I> Parse<I> for Choice<P>
+where
+  P: Parse<I>,
+  I: Clone + Input,
+{
+  type Output = P::Output;
+
+  fn parse(&self, input: I) -> Parser<P::Output, I> {
+    let mut error: Option<ParseError<I>> = None;
+
+    for parser in &self.parsers {
+      match parser.parse(input.clone()) {
+        Parser::NoParse { error: error2 } => {
+          error = Some(match error {
+            Some(error) => error.merge(error2),
+            None => error2,
+          });
+        }
+        p => return p,
+      }
+    }
+
+    Parser::NoParse {
+      // no alternative at all expects nothing
+      error: error.unwrap_or_else(|| ParseError {
+        found: input.found(),
+        input,
+        expected: Vec::new(),
+        context: Vec::new(),
+      }),
+    }
+  }
+}
+
+/// Type-erased parser; see [`ParseExt::boxed`].
+///
+/// [`ParseExt::boxed`]: crate::ParseExt::boxed
+pub struct BoxedParser<'a, A, I> {
+  pub(crate) parser: Box<dyn 'a + Parse<I, Output = A>>,
+}
+
+impl<'a, A, I> Parse<I> for BoxedParser<'a, A, I> {
+  type Output = A;
+
+  fn parse(&self, input: I) -> Parser<A, I> {
+    self.parser.parse(input)
+  }
+}
+
+/// Type-erased parser that can be cloned, sharing the underlying parser; see [`ParseExt::shared`].
+///
+/// [`ParseExt::shared`]: crate::ParseExt::shared
+pub struct SharedParser<'a, A, I> {
+  pub(crate) parser: Rc<dyn 'a + Parse<I, Output = A>>,
+}
+
+impl<'a, A, I> Clone for SharedParser<'a, A, I> {
+  fn clone(&self) -> Self {
+    SharedParser {
+      parser: self.parser.clone(),
+    }
+  }
+}
+
+impl<'a, A, I> Parse<I> for SharedParser<'a, A, I> {
+  type Output = A;
+
+  fn parse(&self, input: I) -> Parser<A, I> {
+    self.parser.parse(input)
+  }
+}
+
+type RecursiveCell<'a, A, I> = OnceCell<Box<dyn 'a + Parse<I, Output = A>>>;
+
+/// Parser referring to itself; see [`recursive`].
+///
+/// [`recursive`]: crate::recursive
+pub struct Recursive<'a, A, I> {
+  pub(crate) parser: RecursiveRef<'a, A, I>,
+}
+
+pub(crate) enum RecursiveRef<'a, A, I> {
+  Owned(Rc<RecursiveCell<'a, A, I>>),
+  /// Reference held by the parser itself, weak so that the parser doesn’t keep itself alive.
+  Weak(Weak<RecursiveCell<'a, A, I>>),
+}
+
+impl<'a, A, I> Clone for Recursive<'a, A, I> {
+  fn clone(&self) -> Self {
+    let parser = match &self.parser {
+      RecursiveRef::Owned(cell) => RecursiveRef::Owned(cell.clone()),
+      RecursiveRef::Weak(cell) => RecursiveRef::Weak(cell.clone()),
+    };
+
+    Recursive { parser }
+  }
+}
+
+impl<'a, A, I> Parse<I> for Recursive<'a, A, I> {
+  type Output = A;
+
+  fn parse(&self, input: I) -> Parser<A, I> {
+    let cell = match &self.parser {
+      RecursiveRef::Owned(cell) => cell.clone(),
+      RecursiveRef::Weak(cell) => cell
+        .upgrade()
+        .expect("recursive parser used after being dropped"),
+    };
+
+    cell
+      .get()
+      .expect("recursive parser used before being defined")
+      .parse(input)
+  }
+}
+
+/// Parser applying a tuple of parsers in sequence; see [`seq`].
+///
+/// [`seq`]: crate::seq
+#[derive(Clone, Debug)]
+pub struct Seq<T> {
+  pub(crate) parsers: T,
+}
+
+/// Parser trying a tuple of parsers in order, until one of them doesn’t fail; see [`alt`].
+///
+/// [`alt`]: crate::alt
+#[derive(Clone, Debug)]
+pub struct Alt<T> {
+  pub(crate) parsers: T,
+}
+
+/// Implement [`Seq`] and [`Alt`] for a tuple of parsers, each given as its type, a name for it and a
+/// name for its value.
+macro_rules! impl_tuple {
+  ($p0:ident $parser0:ident $data0:ident $(, $p:ident $parser:ident $data:ident)*) => {
+    impl<I, $p0, $($p),*> Parse<I> for Seq<($p0, $($p,)*)>
+    where
+      $p0: Parse<I>,
+      $($p: Parse<I>,)*
+    {
+      type Output = (<$p0 as Parse<I>>::Output, $(<$p as Parse<I>>::Output,)*);
+
+      fn parse(&self, input: I) -> Parser<Self::Output, I> {
+        let ($parser0, $($parser,)*) = &self.parsers;
+
+        let ($data0, input) = match $parser0.parse(input) {
+          Parser::Parsed { data, input } => (data, input),
+          failure => return failure.into_failure(),
+        };
+        $(
+          let ($data, input) = match $parser.parse(input) {
+            Parser::Parsed { data, input } => (data, input),
+            failure => return failure.into_failure(),
+          };
+        )*
+
+        Parser::Parsed {
+          data: ($data0, $($data,)*),
+          input,
+        }
+      }
+    }
+
+    impl<I, $p0, $($p),*> Parse<I> for Alt<($p0, $($p,)*)>
+    where
+      I: Clone + Input,
+      $p0: Parse<I>,
+      $($p: Parse<I, Output = <$p0 as Parse<I>>::Output>,)*
+    {
+      type Output = <$p0 as Parse<I>>::Output;
+
+      fn parse(&self, input: I) -> Parser<Self::Output, I> {
+        let ($parser0, $($parser,)*) = &self.parsers;
+
+        let error = match $parser0.parse(input.clone()) {
+          Parser::NoParse { error } => error,
+          p => return p,
+        };
+        $(
+          let error = match $parser.parse(input.clone()) {
+            Parser::NoParse { error: error2 } => error.merge(error2),
+            p => return p,
+          };
+        )*
+
+        Parser::NoParse { error }
+      }
+    }
+  };
+}
+
+/// Implement [`impl_tuple`] for a tuple of parsers and all its suffixes.
+macro_rules! impl_tuples {
+  () => {};
+
+  ($p0:ident $parser0:ident $data0:ident $(, $p:ident $parser:ident $data:ident)*) => {
+    impl_tuple!($p0 $parser0 $data0 $(, $p $parser $data)*);
+    impl_tuples!($($p $parser $data),*);
+  };
+}
+
+impl_tuples!(
+  P0 p0 a0,
+  P1 p1 a1,
+  P2 p2 a2,
+  P3 p3 a3,
+  P4 p4 a4,
+  P5 p5 a5,
+  P6 p6 a6,
+  P7 p7 a7,
+  P8 p8 a8,
+  P9 p9 a9,
+  P10 p10 a10,
+  P11 p11 a11
+);
+
+/// Parser usable as an operand of the composition operators.
+///
+/// Implemented for the parsers of this crate; implement it for a custom parser to compose it with
+/// operators too. Functions and closures have to be wrapped, e.g. with [`TopParser::from_input_parser`].
+pub trait Operand {}
+
+/// Implement [`Seq`] on one more parser for a tuple of parsers and all its suffixes, so that `a + b + c`
+/// parses `(a, b, c)` rather than `((a, b), c)`.
+macro_rules! impl_seq_add {
+  () => {};
+
+  ($p0:ident $parser0:ident $(, $p:ident $parser:ident)*) => {
+    impl<$p0, $($p,)* Rhs: Operand> Add<Rhs> for Seq<($p0, $($p,)*)> {
+      type Output = Seq<($p0, $($p,)* Rhs)>;
+
+      fn add(self, rhs: Rhs) -> Self::Output {
+        let ($parser0, $($parser,)*) = self.parsers;
+
+        Seq {
+          parsers: ($parser0, $($parser,)* rhs),
+        }
+      }
+    }
+
+    impl_seq_add!($($p $parser),*);
+  };
+}
+
+// one less than the largest tuple, which has no room left
+impl_seq_add!(
+  P0 p0,
+  P1 p1,
+  P2 p2,
+  P3 p3,
+  P4 p4,
+  P5 p5,
+  P6 p6,
+  P7 p7,
+  P8 p8,
+  P9 p9,
+  P10 p10
+);
+
+/// Implement [`Operand`] and the composition operators for parser types:
+///
+/// - `a | b` for [`ParseExt::or`].
+/// - `a + b` for [`seq`] on `(a, b)`; sequences take one more parser instead.
+/// - `a >> b` for [`ParseExt::right`].
+/// - `a << b` for [`ParseExt::left`].
+/// - `!a` for [`ParseExt::not_ahead`].
+///
+/// [`ParseExt::or`]: crate::ParseExt::or
+/// [`seq`]: crate::seq
+/// [`ParseExt::right`]: crate::ParseExt::right
+/// [`ParseExt::left`]: crate::ParseExt::left
+/// [`ParseExt::not_ahead`]: crate::ParseExt::not_ahead
+macro_rules! impl_ops {
+  ($([$($g:tt),*] $ty:ty;)*) => {
+    $(
+      impl_ops!(@ [$($g),*] $ty);
+
+      impl<$($g,)* Rhs: Operand> Add<Rhs> for $ty {
+        type Output = Seq<(Self, Rhs)>;
+
+        fn add(self, rhs: Rhs) -> Self::Output {
+          Seq {
+            parsers: (self, rhs),
+          }
+        }
+      }
+    )*
+  };
+
+  (@ [$($g:tt),*] $ty:ty) => {
+    impl<$($g),*> Operand for $ty {}
+
+    impl<$($g,)* Rhs: Operand> BitOr<Rhs> for $ty {
+      type Output = Or<Self, Rhs>;
+
+      fn bitor(self, rhs: Rhs) -> Self::Output {
+        Or {
+          first: self,
+          second: rhs,
+        }
+      }
+    }
+
+    impl<$($g,)* Rhs: Operand> Shr<Rhs> for $ty {
+      type Output = Right<Self, Rhs>;
+
+      fn shr(self, rhs: Rhs) -> Self::Output {
+        Right {
+          first: self,
+          second: rhs,
+        }
+      }
+    }
+
+    impl<$($g,)* Rhs: Operand> Shl<Rhs> for $ty {
+      type Output = Left<Self, Rhs>;
+
+      fn shl(self, rhs: Rhs) -> Self::Output {
+        Left {
+          first: self,
+          second: rhs,
+        }
+      }
+    }
+
+    impl<$($g),*> ops::Not for $ty {
+      type Output = NotAhead<Self>;
+
+      fn not(self) -> Self::Output {
+        NotAhead { parser: self }
+      }
+    }
+  };
+}
+
+impl_ops! {
+  ['a, F, A, I] TopParser<'a, F, A, I>;
+  [P, Q, F] Zip<P, Q, F>;
+  [P, Q] Left<P, Q>;
+  [P, Q] Right<P, Q>;
+  [P, F] AndThen<P, F>;
+  [P, F] Map<P, F>;
+  [P, B] ConstMap<P, B>;
+  [P] Many0<P>;
+  [P] Many1<P>;
+  [P] Opt<P>;
+  [P] Cut<P>;
+  [P, Q] Or<P, Q>;
+  [P] NotAhead<P>;
+  [P] SpannedParser<P>;
+  ['a, P] Recognize<'a, P>;
+  [P] Label<P>;
+  [P] Context<P>;
+  [P, S, F] RecoverWith<P, S, F>;
+  [P, D] Delimited0<P, D>;
+  [P, D] Delimited1<P, D>;
+  [P] Choice<P>;
+  ['a, A, I] BoxedParser<'a, A, I>;
+  ['a, A, I] SharedParser<'a, A, I>;
+  ['a, A, I] Recursive<'a, A, I>;
+  [T] Alt<T>;
+}
+
+impl_ops!(@ [T] Seq<T>);
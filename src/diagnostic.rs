@@ -0,0 +1,337 @@
+use crate::{
+  error::{ExpectedSet, ParseError},
+  input::{Chars, ColumnUnit, LineBasedStr, Span},
+};
+use std::fmt;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+
+/// Rustc-style rendering of a parse error over a [`LineBasedStr`].
+///
+/// The diagnostic shows the line the error occurred on, a caret under what was found and what was
+/// expected instead. When given a span, such as the one of the construct that failed, the diagnostic
+/// underlines it instead, as in `^~~~`. The context of the error and user-provided notes are
+/// appended as notes.
+///
+/// The source must be the whole text the [`LineBasedStr`] was created from, as the error is located in
+/// it by its offset.
+#[derive(Clone, Debug)]
+pub struct Diagnostic<'a, U = Chars> {
+  source: &'a str,
+  file_name: Option<&'a str>,
+  span: Option<Span>,
+  error: ParseError<LineBasedStr<'a, U>>,
+  notes: Vec<String>,
+  colored: bool,
+}
+
+impl<'a, U> Diagnostic<'a, U> {
+  /// Diagnostic of an error that occurred in `source`.
+  pub fn new(source: &'a str, error: ParseError<LineBasedStr<'a, U>>) -> Self {
+    Diagnostic {
+      source,
+      file_name: None,
+      span: None,
+      error,
+      notes: Vec::new(),
+      colored: false,
+    }
+  }
+
+  /// Name the file the source comes from, shown before the line and column of the error.
+  pub fn file_name(self, file_name: &'a str) -> Self {
+    Self {
+      file_name: Some(file_name),
+      ..self
+    }
+  }
+
+  /// Underline a span of the source rather than what the error found.
+  ///
+  /// The diagnostic is then located at the start of the span. Only the part of the span on its first
+  /// line is underlined.
+  pub fn span(self, span: Span) -> Self {
+    Self {
+      span: Some(span),
+      ..self
+    }
+  }
+
+  /// Add a note at the end of the diagnostic.
+  pub fn note(mut self, note: impl Into<String>) -> Self {
+    self.notes.push(note.into());
+    self
+  }
+
+  /// Enable or disable ANSI colours; disabled by default.
+  pub fn colored(self, colored: bool) -> Self {
+    Self { colored, ..self }
+  }
+
+  fn color(&self, color: &'static str) -> &'static str {
+    if self.colored {
+      color
+    } else {
+      ""
+    }
+  }
+}
+
+impl<'a, U> fmt::Display for Diagnostic<'a, U>
+where
+  U: ColumnUnit + PartialEq,
+{
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (reset, bold, red, blue) = (
+      self.color(RESET),
+      self.color(BOLD),
+      self.color(BOLD_RED),
+      self.color(BOLD_BLUE),
+    );
+
+    let boundary = |offset: usize| {
+      let mut offset = offset.min(self.source.len());
+      while !self.source.is_char_boundary(offset) {
+        offset -= 1;
+      }
+
+      offset
+    };
+
+    let (offset, end, col) = match self.span {
+      Some(span) => (
+        boundary(span.start.offset),
+        boundary(span.end.offset),
+        span.start.col,
+      ),
+      None => {
+        let offset = boundary(self.error.offset());
+        let found = self.source[offset..]
+          .chars()
+          .next()
+          .map_or(0, char::len_utf8);
+        (offset, offset + found, self.error.col())
+      }
+    };
+
+    let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = self.source[offset..]
+      .find('\n')
+      .map_or(self.source.len(), |i| offset + i);
+    let source_line = self.source[line_start..line_end].trim_end_matches('\r');
+    let underline = self.source[offset..end.clamp(offset, line_start + source_line.len())]
+      .chars()
+      .count()
+      .max(1);
+
+    // mirror tabs so that the caret lines up with the source line
+    let padding: String = self.source[line_start..offset]
+      .chars()
+      .map(|c| if c == '\t' { '\t' } else { ' ' })
+      .collect();
+
+    let line = (self.source[..line_start].matches('\n').count() + 1).to_string();
+    let gutter = " ".repeat(line.len());
+
+    write!(f, "{}error{}{}: ", red, reset, bold)?;
+    self.error.fmt_message(f)?;
+    writeln!(f, "{}", reset)?;
+
+    write!(f, "{}{}-->{} ", gutter, blue, reset)?;
+
+    if let Some(file_name) = self.file_name {
+      write!(f, "{}:", file_name)?;
+    }
+
+    writeln!(f, "{}:{}", line, col + 1)?;
+    writeln!(f, "{} {}|{}", gutter, blue, reset)?;
+    writeln!(f, "{}{} |{} {}", blue, line, reset, source_line)?;
+    write!(
+      f,
+      "{} {}|{} {}{}^{}",
+      gutter,
+      blue,
+      reset,
+      padding,
+      red,
+      "~".repeat(underline - 1)
+    )?;
+
+    if !self.error.expected.is_empty() {
+      write!(f, " {}", ExpectedSet(&self.error.expected))?;
+    }
+
+    write!(f, "{}", reset)?;
+
+    let notes = self
+      .error
+      .context
+      .iter()
+      .map(|context| format!("in {}", context))
+      .chain(self.notes.iter().cloned())
+      .collect::<Vec<_>>();
+
+    if !notes.is_empty() {
+      write!(f, "\n{} {}|{}", gutter, blue, reset)?;
+
+      for note in notes {
+        write!(
+          f,
+          "\n{} {}={} {}note{}: {}",
+          gutter, blue, reset, bold, reset, note
+        )?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{parse_lexeme, parse_spaces, parse_u32, Parse, ParseExt};
+
+  fn pair_error(source: &str) -> ParseError<LineBasedStr<'_>> {
+    parse_spaces()
+      .right(parse_lexeme("("))
+      .right(parse_spaces())
+      .right(parse_u32())
+      .left(parse_spaces())
+      .left(parse_lexeme(","))
+      .label("pair")
+      .parse(LineBasedStr::from(source))
+      .result()
+      .unwrap_err()
+  }
+
+  #[test]
+  fn render_test() {
+    let source = "\n(  12;";
+    let diag = Diagnostic::new(source, pair_error(source));
+
+    assert_eq!(
+      diag.to_string(),
+      "error: expected `,`, found `;`
+ --> 2:6
+  |
+2 | (  12;
+  |      ^ expected `,`
+  |
+  = note: in pair"
+    );
+  }
+
+  #[test]
+  fn render_expected_set_test() {
+    let source = "true\nfalse\nnope\n";
+    let error = parse_lexeme("true")
+      .or(parse_lexeme("false"))
+      .left(parse_spaces())
+      .many0()
+      .right(parse_lexeme("true").or(parse_lexeme("false")))
+      .parse(LineBasedStr::from(source))
+      .result()
+      .unwrap_err();
+    let diag = Diagnostic::new(source, error).note("booleans are lowercase");
+
+    assert_eq!(
+      diag.to_string(),
+      "error: expected one of: `true`, `false`; found `n`
+ --> 3:1
+  |
+3 | nope
+  | ^ expected one of: `true`, `false`
+  |
+  = note: booleans are lowercase"
+    );
+  }
+
+  #[test]
+  fn render_end_of_input_test() {
+    let source = "(\t1";
+    let diag = Diagnostic::new(source, pair_error(source));
+
+    assert_eq!(
+      diag.to_string(),
+      "error: expected `,`, found end of input
+ --> 1:4
+  |
+1 | (\t1
+  |  \t ^ expected `,`
+  |
+  = note: in pair"
+    );
+  }
+
+  #[test]
+  fn render_span_test() {
+    let source = "let x = (1;\n";
+    let error = pair_error(source);
+    let span = parse_lexeme("let x = (1")
+      .spanned()
+      .parse(LineBasedStr::from(source))
+      .result()
+      .unwrap()
+      .span;
+    let diag = Diagnostic::new(source, error).span(span);
+
+    assert_eq!(
+      diag.to_string(),
+      "error: expected pair, found `l`
+ --> 1:1
+  |
+1 | let x = (1;
+  | ^~~~~~~~~~ expected pair"
+    );
+  }
+
+  #[test]
+  fn render_span_other_line_test() {
+    // the span locates the diagnostic, rather than the error, lines below it
+    let source = "let x = (\n\n  1;\n";
+    let error = parse_lexeme("let x = (")
+      .right(parse_spaces())
+      .right(parse_u32())
+      .left(parse_lexeme(")"))
+      .parse(LineBasedStr::from(source))
+      .result()
+      .unwrap_err();
+    let span = parse_lexeme("let x = ")
+      .right(parse_lexeme("(").spanned())
+      .parse(LineBasedStr::from(source))
+      .result()
+      .unwrap()
+      .span;
+    let diag = Diagnostic::new(source, error).span(span);
+
+    assert_eq!(
+      diag.to_string(),
+      "error: expected `)`, found `;`
+ --> 1:9
+  |
+1 | let x = (
+  |         ^ expected `)`"
+    );
+  }
+
+  #[test]
+  fn render_colored_test() {
+    let source = "(1;";
+    let diag = Diagnostic::new(source, pair_error(source)).colored(true);
+
+    assert_eq!(
+      diag.to_string(),
+      "\x1b[1;31merror\x1b[0m\x1b[1m: expected `,`, found `;`\x1b[0m
+ \x1b[1;34m-->\x1b[0m 1:3
+  \x1b[1;34m|\x1b[0m
+\x1b[1;34m1 |\x1b[0m (1;
+  \x1b[1;34m|\x1b[0m   \x1b[1;31m^ expected `,`\x1b[0m
+  \x1b[1;34m|\x1b[0m
+  \x1b[1;34m=\x1b[0m \x1b[1mnote\x1b[0m: in pair"
+    );
+  }
+}
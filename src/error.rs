@@ -0,0 +1,235 @@
+use crate::input::{ColumnBased, Input, LineBased, OffsetBased, Position, PositionBased};
+use std::{error::Error, fmt};
+
+/// What a parser expected to find when it failed.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Expected {
+  /// A literal lexeme, such as `true` or `[`.
+  Lexeme(String),
+  /// A named class of input, such as “number” or “whitespace.”
+  Named(String),
+}
+
+impl Expected {
+  /// Expect a literal lexeme.
+  pub fn lexeme(l: impl Into<String>) -> Self {
+    Expected::Lexeme(l.into())
+  }
+
+  /// Expect a named class of input.
+  pub fn named(name: impl Into<String>) -> Self {
+    Expected::Named(name.into())
+  }
+}
+
+impl fmt::Display for Expected {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Expected::Lexeme(l) => write!(f, "`{}`", l),
+      Expected::Named(name) => f.write_str(name),
+    }
+  }
+}
+
+/// What a parser found instead of what it expected.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Found {
+  /// A character.
+  Char(char),
+  /// A byte, in binary inputs.
+  Byte(u8),
+  /// A token, in token inputs, as described by [`Token::describe`].
+  ///
+  /// [`Token::describe`]: crate::input::Token::describe
+  Token(String),
+  /// The end of the input.
+  EndOfInput,
+}
+
+impl Found {
+  /// Next character of a string, if any.
+  pub fn next_in(s: &str) -> Self {
+    s.chars().next().map_or(Found::EndOfInput, Found::Char)
+  }
+}
+
+impl fmt::Display for Found {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Found::Char(c) => write!(f, "`{}`", c.escape_debug()),
+      Found::Byte(b) => write!(f, "byte 0x{:02x}", b),
+      Found::Token(t) => f.write_str(t),
+      Found::EndOfInput => f.write_str("end of input"),
+    }
+  }
+}
+
+/// Error produced by a parser that failed.
+///
+/// The input is kept as it was at the point of failure, so that its position can be recovered via the
+/// [`ColumnBased`], [`LineBased`] and [`OffsetBased`] traits.
+///
+/// Several alternatives failing at the same place each contribute to the set of expectations, which is
+/// kept in the order the alternatives were tried.
+///
+/// The context is the stack of labels of the enclosing parsers the error occurred in, innermost first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError<I> {
+  pub input: I,
+  pub expected: Vec<Expected>,
+  pub found: Found,
+  pub context: Vec<String>,
+}
+
+impl<I> ParseError<I>
+where
+  I: Input,
+{
+  /// Create an error for an input, looking up what was found in the input.
+  pub fn new(input: I, expected: Expected) -> Self {
+    let found = input.found();
+
+    ParseError {
+      input,
+      expected: vec![expected],
+      found,
+      context: Vec::new(),
+    }
+  }
+
+  /// Merge two errors coming from alternatives.
+  ///
+  /// The error that made it the furthest into the input wins. If both failed at the same place, their
+  /// expectations and contexts are merged.
+  pub fn merge(mut self, other: Self) -> Self {
+    let remaining = self.input.remaining();
+    let other_remaining = other.input.remaining();
+
+    if remaining < other_remaining {
+      self
+    } else if other_remaining < remaining {
+      other
+    } else {
+      for expected in other.expected {
+        if !self.expected.contains(&expected) {
+          self.expected.push(expected);
+        }
+      }
+
+      for context in other.context {
+        if !self.context.contains(&context) {
+          self.context.push(context);
+        }
+      }
+
+      self
+    }
+  }
+}
+
+impl<I> ParseError<I>
+where
+  I: ColumnBased,
+{
+  /// Column at which the error occurred.
+  pub fn col(&self) -> usize {
+    self.input.col()
+  }
+}
+
+impl<I> ParseError<I>
+where
+  I: LineBased,
+{
+  /// Line at which the error occurred.
+  pub fn line(&self) -> usize {
+    self.input.line()
+  }
+}
+
+impl<I> ParseError<I>
+where
+  I: OffsetBased,
+{
+  /// Offset at which the error occurred.
+  pub fn offset(&self) -> usize {
+    self.input.offset()
+  }
+}
+
+impl<I> ParseError<I>
+where
+  I: PositionBased,
+{
+  /// Position at which the error occurred.
+  pub fn position(&self) -> Position {
+    self.input.position()
+  }
+}
+
+impl<I> ParseError<I> {
+  /// Change the input the error refers to, keeping everything else.
+  pub fn map_input<J>(self, f: impl FnOnce(I) -> J) -> ParseError<J> {
+    ParseError {
+      input: f(self.input),
+      expected: self.expected,
+      found: self.found,
+      context: self.context,
+    }
+  }
+
+  /// Format what was expected and what was found, leaving the context out.
+  pub(crate) fn fmt_message(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.expected.as_slice() {
+      [] => write!(f, "unexpected {}", self.found),
+      [_] => write!(f, "{}, found {}", ExpectedSet(&self.expected), self.found),
+      _ => write!(f, "{}; found {}", ExpectedSet(&self.expected), self.found),
+    }
+  }
+}
+
+impl<I> fmt::Display for ParseError<I> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.fmt_message(f)?;
+
+    for context in &self.context {
+      write!(f, ", in {}", context)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Displayable set of expectations, such as “expected `a`” or “expected one of: `a`, `b`.”
+pub(crate) struct ExpectedSet<'a>(pub(crate) &'a [Expected]);
+
+impl<'a> fmt::Display for ExpectedSet<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.0 {
+      [] => Ok(()),
+      [expected] => write!(f, "expected {}", expected),
+      [first, rest @ ..] => {
+        write!(f, "expected one of: {}", first)?;
+
+        for expected in rest {
+          write!(f, ", {}", expected)?;
+        }
+
+        Ok(())
+      }
+    }
+  }
+}
+
+impl<I> Error for ParseError<I> where I: fmt::Debug {}
+
+/// Outcome of a parser that recovers from errors; see [`Parser::recovered`].
+///
+/// [`Parser::recovered`]: crate::Parser::recovered
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Recovered<A, I> {
+  /// Parsed value, possibly containing placeholders; `None` if the parser failed without recovering.
+  pub data: Option<A>,
+  /// Every error encountered, in order.
+  pub errors: Vec<ParseError<I>>,
+}
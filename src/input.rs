@@ -1,13 +1,78 @@
-/// Class of str-based inputs; allowing to get a &str and advancing it by a given number of characters.
-pub trait StrBased: PartialEq {
+use crate::error::{Found, ParseError};
+use std::{fmt, marker::PhantomData, rc::Rc};
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Class of all inputs; allowing to tell how much of the input is left and what comes next.
+pub trait Input: PartialEq {
+  /// Amount of input left to parse, in the unit of the input (bytes for strings and byte slices).
+  ///
+  /// Used to tell which of two errors made it the furthest into the input.
+  fn remaining(&self) -> usize;
+
+  /// What comes next in the input, for error reporting.
+  fn found(&self) -> Found;
+
+  /// Whether more input may follow the end of this one; `false` by default. See [`Partial`].
+  fn is_partial(&self) -> bool {
+    false
+  }
+}
+
+/// Class of str-based inputs; allowing to get a &str and advancing it by a given number of bytes, which
+/// must fall on a character boundary.
+///
+/// Str-based inputs are [`Input`]s: implementors must implement it as well, typically with the length
+/// of [`StrBased::as_str`] as what remains and [`Found::next_in`] for what comes next.
+pub trait StrBased: Input {
   fn as_str(&self) -> &str;
   fn advance(self, count: usize) -> Self;
 }
 
+/// Class of str-based inputs able to hand out slices of their underlying string living for `'a`, rather
+/// than for as long as the input is borrowed.
+pub trait SliceBased<'a>: StrBased {
+  fn as_slice(&self) -> &'a str;
+}
+
 /// Class of column-based inputs; i.e. accepting the concept of a “column.”
 pub trait ColumnBased: StrBased {
   fn col(&self) -> usize;
   fn set_col(self, col: usize) -> Self;
+
+  /// Number of columns spanned by a string; counted in characters by default.
+  fn col_width(&self, s: &str) -> usize {
+    s.chars().count()
+  }
+
+  /// Number of columns spanned by the first `len` bytes of a string, which may carry on past them.
+  ///
+  /// Defaults to [`ColumnBased::col_width`] of the prefix; units spanning several characters look at
+  /// what follows so that a unit split across two prefixes is counted once.
+  fn prefix_col_width(&self, s: &str, len: usize) -> usize {
+    self.col_width(&s[..len])
+  }
+
+  /// Width between two tab stops; tabs span a single column by default.
+  fn tab_width(&self) -> usize {
+    1
+  }
+
+  /// Column reached after going through the first `len` bytes of a string from a given column,
+  /// expanding tabs to the next tab stop.
+  fn col_after(&self, col: usize, s: &str, len: usize) -> usize {
+    let tab_width = self.tab_width().max(1);
+    let mut col = col;
+    let mut start = 0;
+
+    for (i, _) in s[..len].match_indices('\t') {
+      col += self.prefix_col_width(&s[start..], i - start);
+      col = (col / tab_width + 1) * tab_width;
+      start = i + 1;
+    }
+
+    col + self.prefix_col_width(&s[start..], len - start)
+  }
 }
 
 /// Class of line-based inputs; i.e. accepting the concepts of a “column” and of a “line.”
@@ -16,25 +81,249 @@ pub trait LineBased: ColumnBased {
   fn set_line(self, line: usize) -> Self;
 }
 
+/// Class of offset-based inputs; i.e. knowing their absolute offset, in bytes, from the start of the
+/// source.
+pub trait OffsetBased {
+  fn offset(&self) -> usize;
+
+  /// File the source comes from, if known; see [`InFile`].
+  fn file(&self) -> Option<FileId> {
+    None
+  }
+}
+
+/// Identifier of a file registered in a [`SourceMap`].
+///
+/// [`SourceMap`]: crate::source::SourceMap
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FileId(pub(crate) usize);
+
+/// Position in a source.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+  /// Absolute offset, in bytes.
+  pub offset: usize,
+  pub line: usize,
+  pub col: usize,
+}
+
+impl Position {
+  /// Position an input is at.
+  pub fn of<I>(input: &I) -> Self
+  where
+    I: LineBased + OffsetBased,
+  {
+    Position {
+      offset: input.offset(),
+      line: input.line(),
+      col: input.col(),
+    }
+  }
+}
+
+/// Class of inputs knowing where they are in their source.
+///
+/// Line-based inputs knowing their offset are position-based.
+pub trait PositionBased: OffsetBased {
+  /// Position of what comes next.
+  fn position(&self) -> Position;
+
+  /// Position right after what was consumed last.
+  ///
+  /// It is the position of what comes next, unless the input skips over something between two items,
+  /// such as the whitespace between two tokens.
+  fn end_position(&self) -> Position {
+    self.position()
+  }
+}
+
+impl<I> PositionBased for I
+where
+  I: LineBased + OffsetBased,
+{
+  fn position(&self) -> Position {
+    Position::of(self)
+  }
+}
+
+/// Span of source between two positions; the end position is excluded.
+///
+/// The file is known for spans of inputs coming from a [`SourceMap`]. It is kept in the span rather than
+/// in its positions, since both ends of a span are in the same file, and positions locate something
+/// within a file, so that they compare and order the same way whether the file is known or not.
+///
+/// [`SourceMap`]: crate::source::SourceMap
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+  pub start: Position,
+  pub end: Position,
+  pub file: Option<FileId>,
+}
+
+impl Span {
+  pub fn new(start: Position, end: Position) -> Self {
+    Span {
+      start,
+      end,
+      file: None,
+    }
+  }
+
+  /// Length of the span, in bytes; `0` if the end comes before the start.
+  pub fn len(&self) -> usize {
+    self.end.offset.saturating_sub(self.start.offset)
+  }
+
+  /// Whether the span covers no source at all.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Slice of the source covered by the span, if it lies in the source.
+  pub fn slice<'a>(&self, source: &'a str) -> Option<&'a str> {
+    source.get(self.start.offset..self.end.offset)
+  }
+}
+
+/// Value along with the span of source it was parsed from; see [`ParseExt::spanned`].
+///
+/// [`ParseExt::spanned`]: crate::ParseExt::spanned
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Spanned<A> {
+  pub value: A,
+  pub span: Span,
+}
+
+/// Class of inputs able to record the errors a parser recovered from; see [`ParseExt::recover_with`].
+///
+/// [`ParseExt::recover_with`]: crate::ParseExt::recover_with
+pub trait RecoveryBased: Sized {
+  fn record_error(self, error: ParseError<Self>) -> Self;
+
+  /// Skip what comes next, such as a character, to resynchronize after an error; `None` at the end
+  /// of the input.
+  fn skip(self) -> Option<Self>;
+}
+
+/// Unit in which columns are counted.
+pub trait ColumnUnit {
+  /// Number of columns spanned by a string.
+  fn width(s: &str) -> usize;
+
+  /// Number of columns spanned by the first `len` bytes of a string; see
+  /// [`ColumnBased::prefix_col_width`].
+  fn prefix_width(s: &str, len: usize) -> usize {
+    Self::width(&s[..len])
+  }
+}
+
+/// Columns counted in bytes; the fastest to compute.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Bytes;
+
+impl ColumnUnit for Bytes {
+  fn width(s: &str) -> usize {
+    s.len()
+  }
+}
+
+/// Columns counted in Unicode scalar values, i.e. `char`s.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Chars;
+
+impl ColumnUnit for Chars {
+  fn width(s: &str) -> usize {
+    s.chars().count()
+  }
+}
+
+/// Columns counted in UTF-16 code units, as expected by the Language Server Protocol.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Utf16;
+
+impl ColumnUnit for Utf16 {
+  fn width(s: &str) -> usize {
+    s.encode_utf16().count()
+  }
+}
+
+/// Columns counted in extended grapheme clusters, i.e. what users perceive as characters.
+///
+/// A cluster counts once it ends, so that a cluster split across two slices is counted once. Rules
+/// looking before the start of a slice, such as pairs of regional indicators, are not accounted for.
+///
+/// Requires the `graphemes` feature.
+#[cfg(feature = "graphemes")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Graphemes;
+
+#[cfg(feature = "graphemes")]
+impl ColumnUnit for Graphemes {
+  fn width(s: &str) -> usize {
+    s.graphemes(true).count()
+  }
+
+  fn prefix_width(s: &str, len: usize) -> usize {
+    s.grapheme_indices(true)
+      .take_while(|(i, g)| i + g.len() <= len)
+      .count()
+  }
+}
+
 /// Line-based input around `&str`.
+///
+/// Columns are counted in the unit `U`; see [`ColumnUnit`]. Tabs expand to the next multiple of
+/// `tab_width`, which is `1` by default. The offset is counted in bytes from where the input was created.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct LineBasedStr<'a> {
+pub struct LineBasedStr<'a, U = Chars> {
   pub input: &'a str,
   pub line: usize,
   pub col: usize,
+  pub offset: usize,
+  pub tab_width: usize,
+  pub unit: PhantomData<U>,
 }
 
-impl<'a> From<&'a str> for LineBasedStr<'a> {
-  fn from(input: &'a str) -> Self {
+impl<'a, U> LineBasedStr<'a, U> {
+  pub fn new(input: &'a str) -> Self {
     Self {
       input,
       line: 0,
       col: 0,
+      offset: 0,
+      tab_width: 1,
+      unit: PhantomData,
     }
   }
+
+  pub fn with_tab_width(self, tab_width: usize) -> Self {
+    Self { tab_width, ..self }
+  }
+}
+
+impl<'a> From<&'a str> for LineBasedStr<'a> {
+  fn from(input: &'a str) -> Self {
+    Self::new(input)
+  }
+}
+
+impl<'a, U> Input for LineBasedStr<'a, U>
+where
+  U: PartialEq,
+{
+  fn remaining(&self) -> usize {
+    self.input.len()
+  }
+
+  fn found(&self) -> Found {
+    Found::next_in(self.input)
+  }
 }
 
-impl<'a> StrBased for LineBasedStr<'a> {
+impl<'a, U> StrBased for LineBasedStr<'a, U>
+where
+  U: PartialEq,
+{
   fn as_str(&self) -> &str {
     self.input
   }
@@ -42,12 +331,31 @@ impl<'a> StrBased for LineBasedStr<'a> {
   fn advance(self, count: usize) -> Self {
     Self {
       input: &self.input[count..],
+      offset: self.offset + count,
       ..self
     }
   }
 }
 
-impl<'a> ColumnBased for LineBasedStr<'a> {
+impl<'a, U> OffsetBased for LineBasedStr<'a, U> {
+  fn offset(&self) -> usize {
+    self.offset
+  }
+}
+
+impl<'a, U> SliceBased<'a> for LineBasedStr<'a, U>
+where
+  U: PartialEq,
+{
+  fn as_slice(&self) -> &'a str {
+    self.input
+  }
+}
+
+impl<'a, U> ColumnBased for LineBasedStr<'a, U>
+where
+  U: ColumnUnit + PartialEq,
+{
   fn col(&self) -> usize {
     self.col
   }
@@ -55,9 +363,24 @@ impl<'a> ColumnBased for LineBasedStr<'a> {
   fn set_col(self, col: usize) -> Self {
     Self { col, ..self }
   }
+
+  fn col_width(&self, s: &str) -> usize {
+    U::width(s)
+  }
+
+  fn prefix_col_width(&self, s: &str, len: usize) -> usize {
+    U::prefix_width(s, len)
+  }
+
+  fn tab_width(&self) -> usize {
+    self.tab_width
+  }
 }
 
-impl<'a> LineBased for LineBasedStr<'a> {
+impl<'a, U> LineBased for LineBasedStr<'a, U>
+where
+  U: ColumnUnit + PartialEq,
+{
   fn line(&self) -> usize {
     self.line
   }
@@ -66,3 +389,807 @@ impl<'a> LineBased for LineBasedStr<'a> {
     Self { line, ..self }
   }
 }
+
+impl Input for &str {
+  fn remaining(&self) -> usize {
+    self.len()
+  }
+
+  fn found(&self) -> Found {
+    Found::next_in(self)
+  }
+}
+
+/// Plain `&str` inputs don’t track positions: their column, line and offset are always `0`.
+impl StrBased for &str {
+  fn as_str(&self) -> &str {
+    self
+  }
+
+  fn advance(self, count: usize) -> Self {
+    &self[count..]
+  }
+}
+
+impl<'a> SliceBased<'a> for &'a str {
+  fn as_slice(&self) -> &'a str {
+    self
+  }
+}
+
+impl ColumnBased for &str {
+  fn col(&self) -> usize {
+    0
+  }
+
+  fn set_col(self, _: usize) -> Self {
+    self
+  }
+}
+
+impl LineBased for &str {
+  fn line(&self) -> usize {
+    0
+  }
+
+  fn set_line(self, _: usize) -> Self {
+    self
+  }
+}
+
+impl OffsetBased for &str {
+  fn offset(&self) -> usize {
+    0
+  }
+}
+
+/// Class of byte-based inputs; allowing to get a &[u8] and advancing it by a given number of bytes.
+pub trait ByteBased: Input {
+  fn as_bytes(&self) -> &[u8];
+  fn advance(self, count: usize) -> Self;
+}
+
+/// Class of byte-based inputs able to hand out slices of their underlying bytes living for `'a`, rather
+/// than for as long as the input is borrowed.
+pub trait ByteSliceBased<'a>: ByteBased {
+  fn as_byte_slice(&self) -> &'a [u8];
+}
+
+/// Class of bit-based inputs; i.e. able to read bytes bit by bit.
+pub trait BitBased: ByteBased {
+  /// Bytes left, starting with the one being read, along with the number of its bits already read.
+  fn as_bits(&self) -> (&[u8], usize);
+
+  /// Advance by a given number of bits.
+  fn advance_bits(self, count: usize) -> Self;
+}
+
+/// Plain `&[u8]` inputs don’t track positions: their offset is always `0`.
+impl Input for &[u8] {
+  fn remaining(&self) -> usize {
+    self.len()
+  }
+
+  fn found(&self) -> Found {
+    self.first().copied().map_or(Found::EndOfInput, Found::Byte)
+  }
+}
+
+impl ByteBased for &[u8] {
+  fn as_bytes(&self) -> &[u8] {
+    self
+  }
+
+  fn advance(self, count: usize) -> Self {
+    &self[count..]
+  }
+}
+
+impl<'a> ByteSliceBased<'a> for &'a [u8] {
+  fn as_byte_slice(&self) -> &'a [u8] {
+    self
+  }
+}
+
+impl OffsetBased for &[u8] {
+  fn offset(&self) -> usize {
+    0
+  }
+}
+
+/// Bit-based input around `&[u8]`.
+///
+/// The offset is counted in bytes from where the input was created, and `bit` is the number of bits
+/// already read from the byte at that offset. Bits are read from the most significant one.
+///
+/// Byte-level reads skip what is left of a partially read byte, starting from the next byte boundary.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitBasedBytes<'a> {
+  pub input: &'a [u8],
+  pub offset: usize,
+  pub bit: usize,
+}
+
+impl<'a> BitBasedBytes<'a> {
+  pub fn new(input: &'a [u8]) -> Self {
+    Self {
+      input,
+      offset: 0,
+      bit: 0,
+    }
+  }
+
+  /// Bytes left from the next byte boundary.
+  fn aligned(&self) -> &'a [u8] {
+    &self.input[self.bit.min(1)..]
+  }
+}
+
+impl<'a> From<&'a [u8]> for BitBasedBytes<'a> {
+  fn from(input: &'a [u8]) -> Self {
+    Self::new(input)
+  }
+}
+
+impl<'a> Input for BitBasedBytes<'a> {
+  /// Number of bits left.
+  fn remaining(&self) -> usize {
+    self.input.len() * 8 - self.bit
+  }
+
+  fn found(&self) -> Found {
+    self.input.found()
+  }
+}
+
+impl<'a> ByteBased for BitBasedBytes<'a> {
+  fn as_bytes(&self) -> &[u8] {
+    self.aligned()
+  }
+
+  fn advance(self, count: usize) -> Self {
+    let skipped = self.bit.min(1) + count;
+
+    Self {
+      input: &self.input[skipped..],
+      offset: self.offset + skipped,
+      bit: 0,
+    }
+  }
+}
+
+impl<'a> ByteSliceBased<'a> for BitBasedBytes<'a> {
+  fn as_byte_slice(&self) -> &'a [u8] {
+    self.aligned()
+  }
+}
+
+impl<'a> BitBased for BitBasedBytes<'a> {
+  fn as_bits(&self) -> (&[u8], usize) {
+    (self.input, self.bit)
+  }
+
+  fn advance_bits(self, count: usize) -> Self {
+    let bits = self.bit + count;
+
+    Self {
+      input: &self.input[bits / 8..],
+      offset: self.offset + bits / 8,
+      bit: bits % 8,
+    }
+  }
+}
+
+impl<'a> OffsetBased for BitBasedBytes<'a> {
+  fn offset(&self) -> usize {
+    self.offset
+  }
+}
+
+/// Token produced by a lexer; see [`Tokens`].
+pub trait Token {
+  type Kind: PartialEq + fmt::Debug;
+
+  fn kind(&self) -> &Self::Kind;
+  fn span(&self) -> Span;
+
+  /// Description of the token in error messages; its kind by default.
+  fn describe(&self) -> String {
+    format!("{:?}", self.kind())
+  }
+}
+
+/// Tokens as kinds along with their spans and text, described by their text.
+impl<K> Token for (K, Span, &str)
+where
+  K: PartialEq + fmt::Debug,
+{
+  type Kind = K;
+
+  fn kind(&self) -> &K {
+    &self.0
+  }
+
+  fn span(&self) -> Span {
+    self.1
+  }
+
+  fn describe(&self) -> String {
+    format!("`{}`", self.2.escape_debug())
+  }
+}
+
+/// Class of token-based inputs; allowing to get the tokens left and advancing by a given number of
+/// tokens.
+pub trait TokenBased<'a>: Input {
+  type Token: 'a + Token;
+
+  fn as_tokens(&self) -> &'a [Self::Token];
+  fn advance(self, count: usize) -> Self;
+}
+
+/// Token-based input around `&[T]`.
+///
+/// Positions are taken from the spans of the tokens: the position of the input is the start of the next
+/// token, and its end position is the end of the last token consumed.
+#[derive(Debug)]
+pub struct Tokens<'a, T> {
+  pub tokens: &'a [T],
+  pub end: Position,
+}
+
+impl<'a, T> Tokens<'a, T>
+where
+  T: Token,
+{
+  pub fn new(tokens: &'a [T]) -> Self {
+    let end = tokens
+      .first()
+      .map_or(Position::default(), |t| t.span().start);
+    Self { tokens, end }
+  }
+}
+
+impl<'a, T> From<&'a [T]> for Tokens<'a, T>
+where
+  T: Token,
+{
+  fn from(tokens: &'a [T]) -> Self {
+    Self::new(tokens)
+  }
+}
+
+impl<'a, T> Clone for Tokens<'a, T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, T> Copy for Tokens<'a, T> {}
+
+/// Two token inputs are equal if they are at the same place of the same tokens, which doesn’t require
+/// comparing the tokens themselves.
+impl<'a, T> PartialEq for Tokens<'a, T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.tokens.as_ptr() == other.tokens.as_ptr()
+      && self.tokens.len() == other.tokens.len()
+      && self.end == other.end
+  }
+}
+
+impl<'a, T> Eq for Tokens<'a, T> {}
+
+impl<'a, T> Input for Tokens<'a, T>
+where
+  T: Token,
+{
+  fn remaining(&self) -> usize {
+    self.tokens.len()
+  }
+
+  fn found(&self) -> Found {
+    self
+      .tokens
+      .first()
+      .map_or(Found::EndOfInput, |t| Found::Token(t.describe()))
+  }
+}
+
+impl<'a, T> TokenBased<'a> for Tokens<'a, T>
+where
+  T: 'a + Token,
+{
+  type Token = T;
+
+  fn as_tokens(&self) -> &'a [T] {
+    self.tokens
+  }
+
+  fn advance(self, count: usize) -> Self {
+    let end = match count {
+      0 => self.end,
+      _ => self.tokens[count - 1].span().end,
+    };
+
+    Self {
+      tokens: &self.tokens[count..],
+      end,
+    }
+  }
+}
+
+impl<'a, T> OffsetBased for Tokens<'a, T>
+where
+  T: Token,
+{
+  fn offset(&self) -> usize {
+    self.position().offset
+  }
+
+  fn file(&self) -> Option<FileId> {
+    self.tokens.first().and_then(|t| t.span().file)
+  }
+}
+
+impl<'a, T> PositionBased for Tokens<'a, T>
+where
+  T: Token,
+{
+  fn position(&self) -> Position {
+    self.tokens.first().map_or(self.end, |t| t.span().start)
+  }
+
+  fn end_position(&self) -> Position {
+    self.end
+  }
+}
+
+/// Input wrapper recording the errors recovered from while parsing.
+///
+/// Recorded errors travel with the input, so that they are dropped along with any branch that gets
+/// backtracked from. Two recoverable inputs compare equal if their wrapped inputs do, regardless of the
+/// errors they recorded: equality tells whether a parser consumed input, which recording an error does
+/// not.
+#[derive(Clone, Debug)]
+pub struct Recoverable<I> {
+  pub input: I,
+  errors: Rc<Vec<ParseError<I>>>,
+}
+
+impl<I> Recoverable<I> {
+  pub fn new(input: I) -> Self {
+    Self {
+      input,
+      errors: Rc::new(Vec::new()),
+    }
+  }
+
+  /// Errors recorded so far, in the order they were recovered from.
+  pub fn errors(&self) -> &[ParseError<I>] {
+    &self.errors
+  }
+
+  /// Split into the wrapped input and the recorded errors.
+  pub fn into_parts(self) -> (I, Vec<ParseError<I>>)
+  where
+    I: Clone,
+  {
+    let errors = Rc::try_unwrap(self.errors).unwrap_or_else(|errors| (*errors).clone());
+    (self.input, errors)
+  }
+}
+
+impl<'a> From<&'a str> for Recoverable<LineBasedStr<'a>> {
+  fn from(input: &'a str) -> Self {
+    Self::new(input.into())
+  }
+}
+
+impl<I> PartialEq for Recoverable<I>
+where
+  I: PartialEq,
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.input == other.input
+  }
+}
+
+impl<I> Eq for Recoverable<I> where I: Eq {}
+
+impl<I> Input for Recoverable<I>
+where
+  I: Input,
+{
+  fn remaining(&self) -> usize {
+    self.input.remaining()
+  }
+
+  fn found(&self) -> Found {
+    self.input.found()
+  }
+
+  fn is_partial(&self) -> bool {
+    self.input.is_partial()
+  }
+}
+
+impl<I> StrBased for Recoverable<I>
+where
+  I: StrBased,
+{
+  fn as_str(&self) -> &str {
+    self.input.as_str()
+  }
+
+  fn advance(self, count: usize) -> Self {
+    Self {
+      input: self.input.advance(count),
+      ..self
+    }
+  }
+}
+
+impl<'a, I> SliceBased<'a> for Recoverable<I>
+where
+  I: SliceBased<'a>,
+{
+  fn as_slice(&self) -> &'a str {
+    self.input.as_slice()
+  }
+}
+
+impl<I> ColumnBased for Recoverable<I>
+where
+  I: ColumnBased,
+{
+  fn col(&self) -> usize {
+    self.input.col()
+  }
+
+  fn set_col(self, col: usize) -> Self {
+    Self {
+      input: self.input.set_col(col),
+      ..self
+    }
+  }
+
+  fn col_width(&self, s: &str) -> usize {
+    self.input.col_width(s)
+  }
+
+  fn prefix_col_width(&self, s: &str, len: usize) -> usize {
+    self.input.prefix_col_width(s, len)
+  }
+
+  fn tab_width(&self) -> usize {
+    self.input.tab_width()
+  }
+}
+
+impl<I> LineBased for Recoverable<I>
+where
+  I: LineBased,
+{
+  fn line(&self) -> usize {
+    self.input.line()
+  }
+
+  fn set_line(self, line: usize) -> Self {
+    Self {
+      input: self.input.set_line(line),
+      ..self
+    }
+  }
+}
+
+impl<I> OffsetBased for Recoverable<I>
+where
+  I: OffsetBased,
+{
+  fn offset(&self) -> usize {
+    self.input.offset()
+  }
+
+  fn file(&self) -> Option<FileId> {
+    self.input.file()
+  }
+}
+
+impl<I> RecoveryBased for Recoverable<I>
+where
+  I: Clone + LineBased,
+{
+  fn record_error(mut self, error: ParseError<Self>) -> Self {
+    Rc::make_mut(&mut self.errors).push(error.map_input(|input| input.input));
+    self
+  }
+
+  fn skip(self) -> Option<Self> {
+    let count = self.as_str().chars().next()?.len_utf8();
+    Some(crate::advance_lines(self, count))
+  }
+}
+
+/// Input wrapper marking its input as a chunk of a stream, which more input may follow.
+///
+/// Primitives reaching the end of a partial input fail with [`Parser::Incomplete`], telling how many
+/// more bytes they need at least, instead of assuming the input is over. Callers can then append data
+/// and parse again. Once the stream is over, [`Partial::complete`] switches back to complete-input mode.
+///
+/// [`Parser::Incomplete`]: crate::Parser::Incomplete
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Partial<I> {
+  pub input: I,
+  pub partial: bool,
+}
+
+impl<I> Partial<I> {
+  pub fn new(input: I) -> Self {
+    Self {
+      input,
+      partial: true,
+    }
+  }
+
+  /// Mark the input as the last chunk of the stream.
+  pub fn complete(self) -> Self {
+    Self {
+      partial: false,
+      ..self
+    }
+  }
+}
+
+impl<I> Input for Partial<I>
+where
+  I: Input,
+{
+  fn remaining(&self) -> usize {
+    self.input.remaining()
+  }
+
+  fn found(&self) -> Found {
+    self.input.found()
+  }
+
+  fn is_partial(&self) -> bool {
+    self.partial
+  }
+}
+
+impl<I> StrBased for Partial<I>
+where
+  I: StrBased,
+{
+  fn as_str(&self) -> &str {
+    self.input.as_str()
+  }
+
+  fn advance(self, count: usize) -> Self {
+    Self {
+      input: self.input.advance(count),
+      ..self
+    }
+  }
+}
+
+impl<'a, I> SliceBased<'a> for Partial<I>
+where
+  I: SliceBased<'a>,
+{
+  fn as_slice(&self) -> &'a str {
+    self.input.as_slice()
+  }
+}
+
+impl<I> ColumnBased for Partial<I>
+where
+  I: ColumnBased,
+{
+  fn col(&self) -> usize {
+    self.input.col()
+  }
+
+  fn set_col(self, col: usize) -> Self {
+    Self {
+      input: self.input.set_col(col),
+      ..self
+    }
+  }
+
+  fn col_width(&self, s: &str) -> usize {
+    self.input.col_width(s)
+  }
+
+  fn prefix_col_width(&self, s: &str, len: usize) -> usize {
+    self.input.prefix_col_width(s, len)
+  }
+
+  fn tab_width(&self) -> usize {
+    self.input.tab_width()
+  }
+}
+
+impl<I> LineBased for Partial<I>
+where
+  I: LineBased,
+{
+  fn line(&self) -> usize {
+    self.input.line()
+  }
+
+  fn set_line(self, line: usize) -> Self {
+    Self {
+      input: self.input.set_line(line),
+      ..self
+    }
+  }
+}
+
+impl<I> OffsetBased for Partial<I>
+where
+  I: OffsetBased,
+{
+  fn offset(&self) -> usize {
+    self.input.offset()
+  }
+
+  fn file(&self) -> Option<FileId> {
+    self.input.file()
+  }
+}
+
+impl<I> ByteBased for Partial<I>
+where
+  I: ByteBased,
+{
+  fn as_bytes(&self) -> &[u8] {
+    self.input.as_bytes()
+  }
+
+  fn advance(self, count: usize) -> Self {
+    Self {
+      input: self.input.advance(count),
+      ..self
+    }
+  }
+}
+
+impl<'a, I> ByteSliceBased<'a> for Partial<I>
+where
+  I: ByteSliceBased<'a>,
+{
+  fn as_byte_slice(&self) -> &'a [u8] {
+    self.input.as_byte_slice()
+  }
+}
+
+impl<I> BitBased for Partial<I>
+where
+  I: BitBased,
+{
+  fn as_bits(&self) -> (&[u8], usize) {
+    self.input.as_bits()
+  }
+
+  fn advance_bits(self, count: usize) -> Self {
+    Self {
+      input: self.input.advance_bits(count),
+      ..self
+    }
+  }
+}
+
+/// Input wrapper tagging its input with the file it comes from; see [`SourceMap::input`].
+///
+/// [`SourceMap::input`]: crate::source::SourceMap::input
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InFile<I> {
+  pub input: I,
+  pub file: FileId,
+}
+
+impl<I> InFile<I> {
+  pub fn new(input: I, file: FileId) -> Self {
+    Self { input, file }
+  }
+}
+
+impl<I> Input for InFile<I>
+where
+  I: Input,
+{
+  fn remaining(&self) -> usize {
+    self.input.remaining()
+  }
+
+  fn found(&self) -> Found {
+    self.input.found()
+  }
+
+  fn is_partial(&self) -> bool {
+    self.input.is_partial()
+  }
+}
+
+impl<I> StrBased for InFile<I>
+where
+  I: StrBased,
+{
+  fn as_str(&self) -> &str {
+    self.input.as_str()
+  }
+
+  fn advance(self, count: usize) -> Self {
+    Self {
+      input: self.input.advance(count),
+      ..self
+    }
+  }
+}
+
+impl<'a, I> SliceBased<'a> for InFile<I>
+where
+  I: SliceBased<'a>,
+{
+  fn as_slice(&self) -> &'a str {
+    self.input.as_slice()
+  }
+}
+
+impl<I> ColumnBased for InFile<I>
+where
+  I: ColumnBased,
+{
+  fn col(&self) -> usize {
+    self.input.col()
+  }
+
+  fn set_col(self, col: usize) -> Self {
+    Self {
+      input: self.input.set_col(col),
+      ..self
+    }
+  }
+
+  fn col_width(&self, s: &str) -> usize {
+    self.input.col_width(s)
+  }
+
+  fn prefix_col_width(&self, s: &str, len: usize) -> usize {
+    self.input.prefix_col_width(s, len)
+  }
+
+  fn tab_width(&self) -> usize {
+    self.input.tab_width()
+  }
+}
+
+impl<I> LineBased for InFile<I>
+where
+  I: LineBased,
+{
+  fn line(&self) -> usize {
+    self.input.line()
+  }
+
+  fn set_line(self, line: usize) -> Self {
+    Self {
+      input: self.input.set_line(line),
+      ..self
+    }
+  }
+}
+
+impl<I> OffsetBased for InFile<I>
+where
+  I: OffsetBased,
+{
+  fn offset(&self) -> usize {
+    self.input.offset()
+  }
+
+  fn file(&self) -> Option<FileId> {
+    Some(self.file)
+  }
+}
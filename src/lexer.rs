@@ -0,0 +1,226 @@
+use crate::{
+  error::{Expected, ParseError},
+  input::{LineBasedStr, PositionBased, SliceBased, Span},
+  Parse, ParseExt, Parser,
+};
+
+/// Rule of a lexer; `None` kinds are skipped.
+struct Rule<'a, K, I> {
+  kind: Option<K>,
+  parser: Box<dyn 'a + Fn(I) -> Parser<(), I>>,
+}
+
+/// Lexer turning text into tokens, as a first parsing stage.
+///
+/// Tokens are recognized by rules, which are regular parsers. At each point of the input, the rule
+/// matching the longest text wins; among rules matching equally long texts, the first declared wins, so
+/// that keywords are declared before identifiers. Text matched by skip rules, such as whitespace and
+/// comments, is left out of the tokens.
+///
+/// Tokens come with their spans and text, and can be parsed by a second stage through
+/// [`Tokens`](crate::input::Tokens).
+pub struct Lexer<'a, K, I = LineBasedStr<'a>> {
+  rules: Vec<Rule<'a, K, I>>,
+}
+
+impl<'a, K, I> Default for Lexer<'a, K, I> {
+  fn default() -> Self {
+    Lexer { rules: Vec::new() }
+  }
+}
+
+impl<'a, K, I> Lexer<'a, K, I>
+where
+  K: Clone,
+  I: 'a + Clone + SliceBased<'a> + PositionBased,
+{
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a rule recognizing tokens of a given kind.
+  pub fn token(mut self, kind: K, rule: impl 'a + Parse<I>) -> Self {
+    let rule = rule.const_map(());
+    self.rules.push(Rule {
+      kind: Some(kind),
+      parser: Box::new(move |input| rule.parse(input)),
+    });
+
+    self
+  }
+
+  /// Add a rule recognizing text to skip.
+  pub fn skip(mut self, rule: impl 'a + Parse<I>) -> Self {
+    let rule = rule.const_map(());
+    self.rules.push(Rule {
+      kind: None,
+      parser: Box::new(move |input| rule.parse(input)),
+    });
+
+    self
+  }
+
+  /// Split an input into tokens.
+  ///
+  /// Fails where no rule matches, or with the error of a rule failing fatally.
+  pub fn lex(&self, mut input: I) -> Result<Vec<(K, Span, &'a str)>, ParseError<I>> {
+    let mut tokens = Vec::new();
+
+    while !input.as_str().is_empty() {
+      let mut longest: Option<(&Rule<K, I>, I)> = None;
+
+      for rule in &self.rules {
+        match (rule.parser)(input.clone()) {
+          Parser::Parsed { input: rest, .. } => {
+            // rules matching nothing would never get the lexer anywhere
+            let longer = match &longest {
+              Some((_, longest)) => rest.remaining() < longest.remaining(),
+              None => rest.remaining() < input.remaining(),
+            };
+
+            if longer {
+              longest = Some((rule, rest));
+            }
+          }
+
+          Parser::NoParse { .. } => (),
+          Parser::Fatal { error } | Parser::Incomplete { error, .. } => return Err(error),
+        }
+      }
+
+      let (rule, rest) = match longest {
+        Some(longest) => longest,
+        None => return Err(ParseError::new(input, Expected::named("token"))),
+      };
+
+      if let Some(kind) = &rule.kind {
+        let s = input.as_slice();
+        let text = &s[..s.len() - rest.as_str().len()];
+        let span = Span {
+          file: input.file(),
+          ..Span::new(input.position(), rest.position())
+        };
+
+        tokens.push((kind.clone(), span, text));
+      }
+
+      input = rest;
+    }
+
+    Ok(tokens)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    input::{Position, Tokens},
+    parse_lexeme, parse_while,
+    source::SourceMap,
+    token::parse_token,
+  };
+
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  enum Kind {
+    Let,
+    Ident,
+    Num,
+    Eq,
+    EqEq,
+  }
+
+  type Tok<'a> = (Kind, Span, &'a str);
+
+  fn lexer<'a>() -> Lexer<'a, Kind> {
+    Lexer::new()
+      .token(Kind::Let, parse_lexeme("let"))
+      .token(Kind::Ident, parse_while(char::is_alphabetic))
+      .token(Kind::Num, parse_while(|c| c.is_ascii_digit()))
+      .token(Kind::Eq, parse_lexeme("="))
+      .token(Kind::EqEq, parse_lexeme("=="))
+      .skip(parse_while(char::is_whitespace))
+      .skip(parse_lexeme("#").right(parse_while(|c| c != '\n').opt()))
+  }
+
+  #[test]
+  fn lex_test() {
+    let tokens = lexer()
+      .lex(LineBasedStr::from("let letter = 12 # twelve\nletter == 3"))
+      .unwrap();
+
+    assert_eq!(
+      tokens
+        .iter()
+        .map(|(kind, _, text)| (*kind, *text))
+        .collect::<Vec<_>>(),
+      vec![
+        (Kind::Let, "let"),
+        (Kind::Ident, "letter"),
+        (Kind::Eq, "="),
+        (Kind::Num, "12"),
+        (Kind::Ident, "letter"),
+        (Kind::EqEq, "=="),
+        (Kind::Num, "3"),
+      ]
+    );
+    assert_eq!(
+      tokens[4].1,
+      Span::new(
+        Position {
+          offset: 25,
+          line: 1,
+          col: 0
+        },
+        Position {
+          offset: 31,
+          line: 1,
+          col: 6
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn lex_error_test() {
+    let error = lexer().lex(LineBasedStr::from("let x = 1;")).unwrap_err();
+
+    assert_eq!(error.to_string(), "expected token, found `;`");
+    assert_eq!(error.col(), 9);
+  }
+
+  #[test]
+  fn two_stage_test() {
+    let mut sources = SourceMap::new();
+    let file = sources.add("main.dsl", "let x = y\nlet z");
+    let tokens = Lexer::new()
+      .token(Kind::Let, parse_lexeme("let"))
+      .token(Kind::Ident, parse_while(char::is_alphabetic))
+      .token(Kind::Eq, parse_lexeme("="))
+      .skip(parse_while(char::is_whitespace))
+      .lex(sources.input(file).unwrap())
+      .unwrap();
+
+    let binding = || {
+      parse_token(Kind::Let)
+        .right(parse_token(Kind::Ident))
+        .left(parse_token(Kind::Eq))
+        .zip(parse_token(Kind::Ident), |name: &Tok, value: &Tok| {
+          (name.2, value.2)
+        })
+    };
+    let bindings = binding().many1().parse(Tokens::new(&tokens)).ok();
+
+    assert_eq!(bindings, Some(vec![("x", "y")]));
+    assert_eq!(tokens[4].1.file, Some(file));
+
+    let error = binding()
+      .right(binding())
+      .parse(Tokens::new(&tokens))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.to_string(), "expected Eq, found end of input");
+    assert_eq!(error.position().line, 1);
+  }
+}
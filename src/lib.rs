@@ -1,327 +1,521 @@
+pub mod bytes;
+pub mod combinator;
+pub mod diagnostic;
+pub mod error;
 pub mod input;
-
-use crate::input::{ColumnBased, LineBased};
-use std::marker::PhantomData;
-
-pub struct TopParser<'a, F, A, I>
-where
-  I: 'a,
-  F: 'a,
-{
-  parser: F,
-  _phantom: PhantomData<&'a (A, I)>,
+pub mod lexer;
+pub mod read;
+pub mod source;
+pub mod token;
+
+use crate::{
+  combinator::{
+    Alt, AndThen, BoxedParser, Choice, ConstMap, Context, Cut, Delimited0, Delimited1, Label, Left,
+    Many0, Many1, Map, NotAhead, Opt, Or, Recognize, RecoverWith, Recursive, RecursiveRef, Right,
+    Seq, SharedParser, SpannedParser, Zip,
+  },
+  error::{Expected, Found, ParseError, Recovered},
+  input::{ColumnBased, Input, LineBased, PositionBased, Recoverable, RecoveryBased, SliceBased},
+};
+use std::{cell::OnceCell, iter, marker::PhantomData, rc::Rc};
+
+/// Parser of inputs of type `I`.
+///
+/// Implemented for closures taking an input, for [`TopParser`], for the combinators of [`ParseExt`] and
+/// for any custom parser.
+pub trait Parse<I> {
+  /// Value of the parser.
+  type Output;
+
+  fn parse(&self, input: I) -> Parser<Self::Output, I>;
 }
 
-impl<'a, F, A, I> TopParser<'a, F, A, I>
+impl<F, A, I> Parse<I> for F
 where
-  I: 'a + PartialEq,
-  F: 'a + Fn(I) -> Parser<A, I>,
+  F: Fn(I) -> Parser<A, I>,
 {
-  pub fn from_input_parser(f: F) -> Self {
-    TopParser {
-      parser: f,
-      _phantom: PhantomData,
-    }
-  }
+  type Output = A;
 
-  pub fn parse(&self, input: I) -> Parser<A, I> {
-    (self.parser)(input)
+  fn parse(&self, input: I) -> Parser<A, I> {
+    self(input)
   }
+}
 
-  pub fn zip<B, C>(
-    self,
-    other: TopParser<'a, impl Fn(I) -> Parser<B, I>, B, I>,
-    f: impl 'a + Fn(A, B) -> C,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<C, I>, C, I> {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { data, input } => match (other.parser)(input) {
-          Parser::Parsed { data: data2, input } => Parser::Parsed {
-            data: f(data, data2),
-            input,
-          },
-          Parser::NoParse => Parser::NoParse,
-        },
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
+/// Combinators available on any [`Parse`].
+pub trait ParseExt<I>: Parse<I> + Sized {
+  fn zip<P, F, C>(self, other: P, f: F) -> Zip<Self, P, F>
+  where
+    P: Parse<I>,
+    F: Fn(Self::Output, P::Output) -> C,
+  {
+    Zip {
+      first: self,
+      second: other,
+      f,
     }
   }
 
-  pub fn left<B>(
-    self,
-    other: TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<A, I>, A, I> {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { data, input } => match (other.parser)(input) {
-          Parser::Parsed { input, .. } => Parser::Parsed { data, input },
-          Parser::NoParse => Parser::NoParse,
-        },
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
+  fn left<P>(self, other: P) -> Left<Self, P>
+  where
+    P: Parse<I>,
+  {
+    Left {
+      first: self,
+      second: other,
     }
   }
 
-  pub fn right<B>(
-    self,
-    other: TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I> {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { input, .. } => match (other.parser)(input) {
-          Parser::Parsed { data, input } => Parser::Parsed { data, input },
-          Parser::NoParse => Parser::NoParse,
-        },
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
+  fn right<P>(self, other: P) -> Right<Self, P>
+  where
+    P: Parse<I>,
+  {
+    Right {
+      first: self,
+      second: other,
     }
   }
 
-  pub fn and_then<B, G>(
-    self,
-    f: impl Fn(A) -> TopParser<'a, G, B, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>
+  fn and_then<P, F>(self, f: F) -> AndThen<Self, F>
   where
-    G: 'a + Fn(I) -> Parser<B, I>,
+    P: Parse<I>,
+    F: Fn(Self::Output) -> P,
   {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { data, input } => (f(data).parser)(input),
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
-    }
+    AndThen { parser: self, f }
   }
 
-  pub fn map<B>(
-    self,
-    f: impl 'a + Fn(A) -> B,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I> {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { data, input } => Parser::Parsed {
-          data: f(data),
-          input,
-        },
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
-    }
+  fn map<B, F>(self, f: F) -> Map<Self, F>
+  where
+    F: Fn(Self::Output) -> B,
+  {
+    Map { parser: self, f }
   }
 
-  pub fn const_map<B>(self, b: B) -> TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>
+  fn const_map<B>(self, value: B) -> ConstMap<Self, B>
   where
     B: Clone,
   {
-    TopParser {
-      parser: move |input| match (self.parser)(input) {
-        Parser::Parsed { input, .. } => Parser::Parsed {
-          data: b.clone(),
-          input,
-        },
-        Parser::NoParse => Parser::NoParse,
-      },
-      _phantom: PhantomData,
+    ConstMap {
+      parser: self,
+      value,
     }
   }
 
-  pub fn many0(self) -> TopParser<'a, impl 'a + Fn(I) -> Parser<Vec<A>, I>, Vec<A>, I>
+  fn many0(self) -> Many0<Self>
   where
-    I: Clone,
+    I: Clone + PartialEq,
   {
-    TopParser {
-      parser: move |mut i: I| {
-        let mut results = Vec::new();
+    Many0 { parser: self }
+  }
 
-        while let Parser::Parsed { data, input } = (self.parser)(i.clone()) {
-          if input == i {
-            // input hasn’t changed, which might indicate that the parser didn’t consume; break
-            break;
-          }
+  fn many1(self) -> Many1<Self>
+  where
+    I: Clone + Input,
+  {
+    Many1 { parser: self }
+  }
 
-          results.push(data);
-          i = input;
-        }
+  fn opt(self) -> Opt<Self>
+  where
+    I: Clone,
+  {
+    Opt { parser: self }
+  }
 
-        Parser::Parsed {
-          input: i,
-          data: results,
-        }
-      },
-      _phantom: PhantomData,
-    }
+  /// Commit to this parser.
+  ///
+  /// If the parser fails, the failure is turned into a fatal one, preventing enclosing combinators such
+  /// as [`ParseExt::or`], [`ParseExt::opt`], [`ParseExt::many0`] or [`ParseExt::delimited0`] from
+  /// backtracking and trying something else.
+  fn cut(self) -> Cut<Self> {
+    Cut { parser: self }
   }
 
-  pub fn many1(self) -> TopParser<'a, impl 'a + Fn(I) -> Parser<Vec<A>, I>, Vec<A>, I>
+  fn or<P>(self, other: P) -> Or<Self, P>
   where
-    I: Clone,
+    P: Parse<I, Output = Self::Output>,
+    I: Clone + Input,
   {
-    TopParser {
-      parser: move |mut i: I| {
-        let mut results = Vec::new();
+    Or {
+      first: self,
+      second: other,
+    }
+  }
 
-        while let Parser::Parsed { data, input } = (self.parser)(i.clone()) {
-          // input hasn’t changed, which might indicate that the parser didn’t consume; break
-          if input == i {
-            break;
-          }
+  /// Negative lookahead: succeed without consuming anything if this parser fails, and fail if it
+  /// succeeds.
+  fn not_ahead(self) -> NotAhead<Self>
+  where
+    I: Clone + Input,
+  {
+    NotAhead { parser: self }
+  }
 
-          results.push(data);
-          i = input;
-        }
+  /// Wrap the value of this parser along with the span of what it consumed.
+  fn spanned(self) -> SpannedParser<Self>
+  where
+    I: PositionBased,
+  {
+    SpannedParser { parser: self }
+  }
 
-        if results.is_empty() {
-          Parser::NoParse
-        } else {
-          Parser::Parsed {
-            input: i,
-            data: results,
-          }
-        }
-      },
+  /// Discard the value of this parser and return the slice of input it consumed instead.
+  fn recognize<'a>(self) -> Recognize<'a, Self>
+  where
+    I: SliceBased<'a>,
+  {
+    Recognize {
+      parser: self,
       _phantom: PhantomData,
     }
   }
 
-  pub fn opt(self) -> TopParser<'a, impl 'a + Fn(I) -> Parser<Option<A>, I>, Option<A>, I>
+  /// Name this parser.
+  ///
+  /// If the parser fails without consuming any input, the label replaces whatever it expected. If it
+  /// fails further in, the label is added to the context of the error.
+  fn label(self, label: impl Into<String>) -> Label<Self>
   where
-    I: Clone,
+    I: Clone + PartialEq,
   {
-    TopParser {
-      parser: move |input: I| match (self.parser)(input.clone()) {
-        Parser::Parsed { data, input } => Parser::Parsed {
-          data: Some(data),
-          input,
-        },
-        Parser::NoParse => Parser::Parsed { data: None, input },
-      },
-      _phantom: PhantomData,
+    Label {
+      parser: self,
+      label: label.into(),
     }
   }
 
-  pub fn or(
-    self,
-    other: TopParser<'a, impl 'a + Fn(I) -> Parser<A, I>, A, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<A, I>, A, I>
+  /// Add a context to errors coming from this parser, if it failed after consuming some input.
+  ///
+  /// Unlike [`ParseExt::label`], the expectations are left untouched.
+  fn context(self, context: impl Into<String>) -> Context<Self>
   where
-    I: Clone,
+    I: Clone + PartialEq,
   {
-    TopParser {
-      parser: move |input: I| match (self.parser)(input.clone()) {
-        Parser::NoParse => (other.parser)(input),
-        p => p,
-      },
-      _phantom: PhantomData,
+    Context {
+      parser: self,
+      context: context.into(),
+    }
+  }
+
+  /// Recover from failures of this parser.
+  ///
+  /// On failure, the error is recorded in the input, which is then skipped until `sync` succeeds. The
+  /// skipping starts from where the error occurred, i.e. the input of the error, rather than from where
+  /// this parser started. What `sync` recognizes is left unconsumed, so that enclosing parsers can carry
+  /// on from there. The value is replaced by a placeholder built from the error.
+  ///
+  /// Fatal errors are not recovered from, but propagated unchanged.
+  fn recover_with<S, F>(self, sync: S, placeholder: F) -> RecoverWith<Self, S, F>
+  where
+    S: Parse<I>,
+    F: Fn(&ParseError<I>) -> Self::Output,
+    I: Clone + RecoveryBased,
+  {
+    RecoverWith {
+      parser: self,
+      sync,
+      placeholder,
     }
   }
 
-  pub fn delimited0<B>(
-    self,
-    delimiter: TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<Vec<A>, I>, Vec<A>, I>
+  fn delimited0<D>(self, delimiter: D) -> Delimited0<Self, D>
   where
+    D: Parse<I>,
     I: Clone,
   {
-    TopParser::from_input_parser(move |mut i: I| {
-      let mut even = true;
-      let mut results = Vec::new();
-
-      loop {
-        if even {
-          if let Parser::Parsed { data, input } = self.parse(i.clone()) {
-            results.push(data);
-            i = input;
-          } else {
-            break;
-          }
-        } else {
-          if let Parser::Parsed { input, .. } = delimiter.parse(i.clone()) {
-            i = input;
-          } else {
-            break;
-          }
-        }
+    Delimited0 {
+      parser: self,
+      delimiter,
+    }
+  }
 
-        even = !even;
-      }
+  fn delimited1<D>(self, delimiter: D) -> Delimited1<Self, D>
+  where
+    D: Parse<I>,
+    I: Clone,
+  {
+    Delimited1 {
+      parser: self,
+      delimiter,
+    }
+  }
 
-      if !even || results.is_empty() {
-        Parser::Parsed {
-          data: results,
-          input: i,
-        }
-      } else {
-        Parser::NoParse
-      }
-    })
+  /// Erase the type of this parser, so that parsers of different types can be mixed, for instance
+  /// in grammars built at runtime.
+  fn boxed<'a>(self) -> BoxedParser<'a, Self::Output, I>
+  where
+    Self: 'a,
+  {
+    BoxedParser {
+      parser: Box::new(self),
+    }
   }
 
-  pub fn delimited1<B>(
-    self,
-    delimiter: TopParser<'a, impl 'a + Fn(I) -> Parser<B, I>, B, I>,
-  ) -> TopParser<'a, impl 'a + Fn(I) -> Parser<Vec<A>, I>, Vec<A>, I>
+  /// Erase the type of this parser, like [`ParseExt::boxed`], but as a parser that can be cloned.
+  fn shared<'a>(self) -> SharedParser<'a, Self::Output, I>
   where
-    I: Clone,
+    Self: 'a,
   {
-    TopParser::from_input_parser(move |mut i: I| {
-      let mut even = true;
-      let mut results = Vec::new();
-
-      loop {
-        if even {
-          if let Parser::Parsed { data, input } = self.parse(i.clone()) {
-            results.push(data);
-            i = input;
-          } else {
-            break;
-          }
-        } else {
-          if let Parser::Parsed { input, .. } = delimiter.parse(i.clone()) {
-            i = input;
-          } else {
-            break;
-          }
-        }
+    SharedParser {
+      parser: Rc::new(self),
+    }
+  }
+}
 
-        even = !even;
-      }
+impl<P, I> ParseExt<I> for P where P: Parse<I> {}
 
-      if even {
-        Parser::NoParse
-      } else {
-        Parser::Parsed {
-          data: results,
-          input: i,
-        }
-      }
-    })
+/// Try parsers in order, until one of them doesn’t fail.
+///
+/// Unlike [`ParseExt::or`], the number of alternatives doesn’t have to be known at compile time; they
+/// are typically [`BoxedParser`]s. Errors of alternatives failing are merged, as with
+/// [`ParseExt::or`].
+pub fn choice<P, I>(parsers: Vec<P>) -> Choice<P>
+where
+  P: Parse<I>,
+  I: Clone + Input,
+{
+  Choice { parsers }
+}
+
+/// Apply a tuple of parsers in sequence, returning the tuple of their values.
+///
+/// Implemented for tuples of up to 12 parsers, of any types.
+pub fn seq<T>(parsers: T) -> Seq<T> {
+  Seq { parsers }
+}
+
+/// Try a tuple of parsers in order, until one of them doesn’t fail.
+///
+/// Implemented for tuples of up to 12 parsers, of any types but with the same value type. Errors of
+/// alternatives failing are merged, as with [`ParseExt::or`].
+pub fn alt<T>(parsers: T) -> Alt<T> {
+  Alt { parsers }
+}
+
+/// Define a parser referring to itself, such as a parser of nested expressions.
+///
+/// `f` is given a handle on the parser being defined, to use wherever the parser recurses, and returns
+/// the definition of the parser. The parser is built once, and the returned handle can be cloned and
+/// reused freely. The handle given to `f` is only valid as long as the returned one is alive.
+///
+/// # Panics
+///
+/// Parsing with the handle given to `f` panics if done before `f` returns.
+pub fn recursive<'a, A, I, P>(f: impl FnOnce(Recursive<'a, A, I>) -> P) -> Recursive<'a, A, I>
+where
+  P: 'a + Parse<I, Output = A>,
+{
+  let cell = Rc::new(OnceCell::new());
+  let parser = f(Recursive {
+    parser: RecursiveRef::Weak(Rc::downgrade(&cell)),
+  });
+
+  // the cell was just created, so it can’t be defined already
+  let _ = cell.set(Box::new(parser));
+
+  Recursive {
+    parser: RecursiveRef::Owned(cell),
+  }
+}
+
+/// Parser wrapping a closure, as returned by the primitives of this crate.
+///
+/// Like the combinators of [`ParseExt`], it composes with operators: `a | b` is `a.or(b)`, `a + b` is
+/// `seq((a, b))`, `a >> b` is `a.right(b)`, `a << b` is `a.left(b)` and `!a` is `a.not_ahead()`.
+pub struct TopParser<'a, F, A, I>
+where
+  I: 'a,
+  F: 'a,
+{
+  parser: F,
+  _phantom: PhantomData<&'a (A, I)>,
+}
+
+impl<'a, F, A, I> TopParser<'a, F, A, I>
+where
+  I: 'a,
+  F: 'a + Fn(I) -> Parser<A, I>,
+{
+  pub fn from_input_parser(f: F) -> Self {
+    TopParser {
+      parser: f,
+      _phantom: PhantomData,
+    }
+  }
+
+  pub fn parse(&self, input: I) -> Parser<A, I> {
+    (self.parser)(input)
+  }
+}
+
+impl<'a, F, A, I> Parse<I> for TopParser<'a, F, A, I>
+where
+  F: Fn(I) -> Parser<A, I>,
+{
+  type Output = A;
+
+  fn parse(&self, input: I) -> Parser<A, I> {
+    (self.parser)(input)
   }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Parser<A, I> {
-  Parsed { data: A, input: I },
-  NoParse,
+  Parsed {
+    data: A,
+    input: I,
+  },
+  NoParse {
+    error: ParseError<I>,
+  },
+  /// Failure that must not be backtracked from; see [`ParseExt::cut`].
+  Fatal {
+    error: ParseError<I>,
+  },
+  /// Failure caused by a partial input ending too early; see [`Partial`].
+  ///
+  /// At least `needed` more bytes are required to carry on.
+  ///
+  /// [`Partial`]: crate::input::Partial
+  Incomplete {
+    error: ParseError<I>,
+    needed: usize,
+  },
 }
 
-impl<'a, A, I> Parser<A, I> {
+impl<A, I> Parser<A, I> {
   pub fn ok(self) -> Option<A> {
     match self {
       Parser::Parsed { data, .. } => Some(data),
-      Parser::NoParse => None,
+      Parser::NoParse { .. } | Parser::Fatal { .. } | Parser::Incomplete { .. } => None,
+    }
+  }
+
+  pub fn result(self) -> Result<A, ParseError<I>> {
+    match self {
+      Parser::Parsed { data, .. } => Ok(data),
+      Parser::NoParse { error } | Parser::Fatal { error } | Parser::Incomplete { error, .. } => {
+        Err(error)
+      }
+    }
+  }
+
+  /// Map the value of a successful parser, passing failures on unchanged.
+  pub fn map<B>(self, f: impl FnOnce(A) -> B) -> Parser<B, I> {
+    self.and_then(|data, input| Parser::Parsed {
+      data: f(data),
+      input,
+    })
+  }
+
+  /// Carry on from a successful parser with its value and the input it left, passing failures on
+  /// unchanged.
+  pub fn and_then<B>(self, f: impl FnOnce(A, I) -> Parser<B, I>) -> Parser<B, I> {
+    match self {
+      Parser::Parsed { data, input } => f(data, input),
+      failure => failure.into_failure(),
+    }
+  }
+
+  /// Same failure, as a failure of a parser of another value.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the parser succeeded.
+  pub(crate) fn into_failure<B>(self) -> Parser<B, I> {
+    match self {
+      Parser::Parsed { .. } => panic!("not a failure"),
+      Parser::NoParse { error } => Parser::NoParse { error },
+      Parser::Fatal { error } => Parser::Fatal { error },
+      Parser::Incomplete { error, needed } => Parser::Incomplete { error, needed },
+    }
+  }
+
+  /// Map the error of a failed parser, whatever the kind of failure.
+  pub fn map_error(self, f: impl FnOnce(ParseError<I>) -> ParseError<I>) -> Self {
+    match self {
+      Parser::NoParse { error } => Parser::NoParse { error: f(error) },
+      Parser::Fatal { error } => Parser::Fatal { error: f(error) },
+      Parser::Incomplete { error, needed } => Parser::Incomplete {
+        error: f(error),
+        needed,
+      },
+      p => p,
+    }
+  }
+}
+
+impl<A, I> Parser<A, I>
+where
+  I: Input,
+{
+  /// Turn an optional parser into a parser, failing with `error` if there is none.
+  pub fn from_option(parser: Option<Self>, error: ParseError<I>) -> Self {
+    parser.unwrap_or(Parser::NoParse { error })
+  }
+
+  /// Failure of a parser that ran out of input.
+  ///
+  /// If more input may follow, the failure is [`Parser::Incomplete`], needing at least `needed` more
+  /// bytes.
+  pub fn end_of_input(input: I, expected: Expected, needed: usize) -> Self {
+    let error = ParseError {
+      found: Found::EndOfInput,
+      ..ParseError::new(input, expected)
+    };
+
+    if error.input.is_partial() {
+      Parser::Incomplete { error, needed }
+    } else {
+      Parser::NoParse { error }
     }
   }
 }
 
-impl<'a, A, I> From<Option<Parser<A, I>>> for Parser<A, I> {
-  fn from(x: Option<Parser<A, I>>) -> Self {
-    x.unwrap_or_else(|| Parser::NoParse)
+impl<A, I> Parser<A, Recoverable<I>>
+where
+  I: Clone,
+{
+  /// Gather the value and every error recovered from, including the one the parser failed with, if any.
+  pub fn recovered(self) -> Recovered<A, I> {
+    match self {
+      Parser::Parsed { data, input } => Recovered {
+        data: Some(data),
+        errors: input.into_parts().1,
+      },
+
+      Parser::NoParse { error } | Parser::Fatal { error } | Parser::Incomplete { error, .. } => {
+        let (input, mut errors) = error.input.into_parts();
+        errors.push(ParseError {
+          input,
+          expected: error.expected,
+          found: error.found,
+          context: error.context,
+        });
+
+        Recovered { data: None, errors }
+      }
+    }
   }
 }
 
+/// Advance a line-based input by a given number of bytes, updating its line and column.
+fn advance_lines<I>(input: I, count: usize) -> I
+where
+  I: LineBased,
+{
+  let s = input.as_str();
+
+  let (line, col) = match s[..count].rfind('\n') {
+    Some(i) => (
+      input.line() + s[..count].matches('\n').count(),
+      input.col_after(0, &s[i + 1..], count - i - 1),
+    ),
+    None => (input.line(), input.col_after(input.col(), s, count)),
+  };
+
+  input.set_line(line).set_col(col).advance(count)
+}
+
 pub fn parse_u32<'a, I>() -> TopParser<'a, impl Fn(I) -> Parser<u32, I>, u32, I>
 where
   I: 'a + ColumnBased,
@@ -336,61 +530,67 @@ where
       count += 1;
     }
 
-    s[..count]
-      .parse()
-      .ok()
-      .map(|data| {
-        let col = input.col() + count;
+    if count == len && input.is_partial() {
+      // digits might carry on past the end of the input
+      return Parser::end_of_input(input, Expected::named("unsigned integer"), 1);
+    }
+
+    match s[..count].parse() {
+      Ok(data) => {
+        let col = input.col_after(input.col(), s, count);
         let input = input.set_col(col).advance(count);
         Parser::Parsed { data, input }
-      })
-      .into()
+      }
+
+      // there are digits, but too many of them to fit
+      Err(_) if count > 0 => Parser::NoParse {
+        error: ParseError::new(
+          input,
+          Expected::named("unsigned integer fitting in 32 bits"),
+        ),
+      },
+
+      Err(_) => Parser::NoParse {
+        error: ParseError::new(input, Expected::named("unsigned integer")),
+      },
+    }
   })
 }
 
+/// Skip ASCII whitespace.
+///
+/// On a [`Partial`] input made only of whitespace, more whitespace may follow: the parser then fails
+/// with [`Parser::Incomplete`] rather than succeeding, until the input gets more data or is marked as
+/// complete. Skip whitespace before what it separates rather than after it, so that reading a stream
+/// doesn’t wait for what follows the last value.
+///
+/// [`Partial`]: crate::input::Partial
 pub fn parse_spaces<'a, I>() -> TopParser<'a, impl Fn(I) -> Parser<(), I>, (), I>
 where
   I: 'a + LineBased,
 {
   TopParser::from_input_parser(|input: I| {
-    let s = input.as_str();
-    let mut line = input.line();
-    let mut col = input.col();
-
-    let mut count = 0;
-    let len = s.len();
-    let bytes = s.as_bytes();
-
-    while count < len {
-      let b = bytes[count];
-
-      if !b.is_ascii_whitespace() {
-        break;
-      }
-
-      if b == b'\n' {
-        line += 1;
-        col = 0;
-      } else if b == b'\r' && count < len - 1 && bytes[count + 1] == b'\n' {
-        line += 1;
-        col = 0;
-        count += 1; // to take into account the \r + \n
-      } else {
-        col += 1;
-      }
-
-      count += 1;
+    let count = input
+      .as_str()
+      .bytes()
+      .take_while(u8::is_ascii_whitespace)
+      .count();
+
+    if count == input.as_str().len() && input.is_partial() {
+      return Parser::end_of_input(input, Expected::named("end of whitespace"), 1);
     }
 
-    let input = input.set_line(line).set_col(col).advance(count);
+    let input = advance_lines(input, count);
 
     Parser::Parsed { data: (), input }
   })
 }
 
-pub fn parse_lexeme<'a, I>(l: impl AsRef<str>) -> TopParser<'a, impl Fn(I) -> Parser<(), I>, (), I>
+pub fn parse_lexeme<'a, I>(
+  l: impl 'a + AsRef<str>,
+) -> TopParser<'a, impl Fn(I) -> Parser<(), I>, (), I>
 where
-  I: 'a + ColumnBased,
+  I: 'a + LineBased,
 {
   TopParser {
     parser: move |input: I| {
@@ -398,50 +598,86 @@ where
       let s = input.as_str();
 
       if s.starts_with(l) {
-        let count = l.len();
-        let col = input.col() + count;
-        let input = input.set_col(col).advance(count);
-
+        let input = advance_lines(input, l.len());
         Parser::Parsed { data: (), input }
+      } else if input.is_partial() && l.starts_with(s) {
+        let needed = l.len() - s.len();
+        Parser::end_of_input(input, Expected::lexeme(l), needed)
       } else {
-        Parser::NoParse
+        Parser::NoParse {
+          error: ParseError::new(input, Expected::lexeme(l)),
+        }
       }
     },
     _phantom: PhantomData,
   }
 }
 
-pub fn parse_take<'a>(
+/// Take the next `count` characters.
+///
+/// The count is in characters rather than bytes, so that what is taken never splits a character. To
+/// take bytes, see [`bytes::parse_take`] over byte inputs.
+pub fn parse_take<'a, I>(
   count: usize,
-) -> TopParser<'a, impl Fn(&'a str) -> Parser<&'a str, &'a str>, &'a str, &'a str> {
+) -> TopParser<'a, impl Fn(I) -> Parser<&'a str, I>, &'a str, I>
+where
+  I: 'a + SliceBased<'a> + LineBased,
+{
   TopParser {
-    parser: move |input: &'a str| {
-      if input.len() >= count {
-        Parser::Parsed {
-          data: &input[..count],
-          input: &input[count..],
-        }
+    parser: move |input: I| {
+      let s = input.as_slice();
+
+      // byte offset of the boundary after the count-th character, if any
+      let offset = s
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(iter::once(s.len()))
+        .nth(count);
+
+      if let Some(offset) = offset {
+        Parser::Parsed {
+          data: &s[..offset],
+          input: advance_lines(input, offset),
+        }
       } else {
-        Parser::NoParse
+        // each missing character takes at least a byte
+        let needed = count - s.chars().count();
+        Parser::end_of_input(
+          input,
+          Expected::named(format!("{} characters", count)),
+          needed,
+        )
       }
     },
     _phantom: PhantomData,
   }
 }
 
-pub fn parse_while<'a>(
-  predicate: impl Fn(char) -> bool,
-) -> TopParser<'a, impl Fn(&'a str) -> Parser<&'a str, &'a str>, &'a str, &'a str> {
+pub fn parse_while<'a, I>(
+  predicate: impl 'a + Fn(char) -> bool,
+) -> TopParser<'a, impl Fn(I) -> Parser<&'a str, I>, &'a str, I>
+where
+  I: 'a + SliceBased<'a> + LineBased,
+{
   TopParser {
-    parser: move |input: &'a str| {
-      let count = input.chars().take_while(|c| predicate(*c)).count();
-
-      if count == 0 {
-        Parser::NoParse
+    parser: move |input: I| {
+      let s = input.as_slice();
+      let count = s
+        .char_indices()
+        .find(|(_, c)| !predicate(*c))
+        .map_or(s.len(), |(i, _)| i);
+
+      if count == s.len() && input.is_partial() {
+        // matching characters might carry on past the end of the input
+        Parser::end_of_input(input, Expected::named("non-matching character"), 1)
+      } else if count == 0 {
+        Parser::NoParse {
+          error: ParseError::new(input, Expected::named("matching character")),
+        }
       } else {
         Parser::Parsed {
-          data: &input[..count],
-          input: &input[count..],
+          data: &s[..count],
+          input: advance_lines(input, count),
         }
       }
     },
@@ -452,7 +688,12 @@ pub fn parse_while<'a>(
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::input::LineBasedStr;
+  #[cfg(feature = "graphemes")]
+  use crate::input::Graphemes;
+  use crate::input::{
+    Bytes, Chars, ColumnUnit, LineBasedStr, Partial, Position, Span, Spanned, Utf16,
+  };
+  use proptest::{prop_assert, prop_assert_eq, proptest};
 
   #[test]
   fn parse_u32_test() {
@@ -464,9 +705,21 @@ mod tests {
           input: "lol",
           line: 0,
           col: 3,
+          offset: 3,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
+
+    assert_eq!(
+      parse_u32()
+        .parse(LineBasedStr::from("99999999999"))
+        .result()
+        .unwrap_err()
+        .to_string(),
+      "expected unsigned integer fitting in 32 bits, found `9`"
+    );
   }
 
   #[test]
@@ -479,6 +732,9 @@ mod tests {
           input: "lol",
           line: 0,
           col: 7,
+          offset: 7,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
@@ -494,11 +750,71 @@ mod tests {
           input: "lol",
           line: 2,
           col: 4,
+          offset: 9,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
   }
 
+  #[test]
+  fn parse_spaces_with_tabs_test() {
+    let input = LineBasedStr::from(" \t  \t\n\t  \tlol").with_tab_width(4);
+
+    assert_eq!(
+      parse_spaces().parse(input.clone()),
+      Parser::Parsed {
+        data: (),
+        input: LineBasedStr {
+          input: "lol",
+          line: 1,
+          col: 8,
+          offset: 10,
+          tab_width: 4,
+          unit: PhantomData,
+        }
+      }
+    );
+
+    let parser = parse_spaces().right(parse_lexeme("lol"));
+
+    assert_eq!(
+      parser.parse(input.with_tab_width(8)),
+      Parser::Parsed {
+        data: (),
+        input: LineBasedStr {
+          input: "",
+          line: 1,
+          col: 19,
+          offset: 13,
+          tab_width: 8,
+          unit: PhantomData,
+        }
+      }
+    );
+  }
+
+  #[test]
+  fn tabs_in_primitives_test() {
+    let parser = parse_lexeme("a\tb")
+      .right(parse_while(|c| c != '!'))
+      .right(parse_u32());
+    let error = parser
+      .parse(LineBasedStr::from("a\tbc\t\td!").with_tab_width(4))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 13);
+
+    let error = parser
+      .parse(LineBasedStr::from("a\tb\n\t12\t!").with_tab_width(4))
+      .result()
+      .unwrap_err();
+
+    assert_eq!((error.line(), error.col()), (1, 8));
+  }
+
   #[test]
   fn and_then_test() {
     let parser = parse_u32()
@@ -513,6 +829,9 @@ mod tests {
           input: "lol",
           line: 2,
           col: 5,
+          offset: 11,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
@@ -531,7 +850,10 @@ mod tests {
         input: LineBasedStr {
           input: "lol",
           line: 3,
-          col: 1
+          col: 1,
+          offset: 11,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
@@ -544,6 +866,9 @@ mod tests {
           input: "lol",
           line: 0,
           col: 0,
+          offset: 0,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
@@ -563,11 +888,49 @@ mod tests {
           input: "lol",
           line: 0,
           col: 8,
+          offset: 8,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
 
-    assert_eq!(parser.parse(LineBasedStr::from("lol")), Parser::NoParse);
+    assert_eq!(
+      parser.parse(LineBasedStr::from("lol")),
+      Parser::NoParse {
+        error: ParseError {
+          input: LineBasedStr::from("lol"),
+          expected: vec![Expected::named("unsigned integer")],
+          found: Found::Char('l'),
+          context: Vec::new(),
+        }
+      }
+    );
+  }
+
+  #[test]
+  fn many1_no_progress_test() {
+    let parser = parse_u32().opt().many1();
+
+    assert_eq!(
+      parser.parse("lol").result().unwrap_err().to_string(),
+      "expected non-empty match, found `l`"
+    );
+    assert_eq!(parser.parse("12lol").ok(), Some(vec![Some(12)]));
+  }
+
+  #[test]
+  fn from_option_test() {
+    let error = || ParseError::new("lol", Expected::named("number"));
+
+    assert_eq!(
+      Parser::from_option(None::<Parser<u32, _>>, error()),
+      Parser::NoParse { error: error() }
+    );
+    assert_eq!(
+      Parser::from_option(parse_u32().parse("1").into(), error()).ok(),
+      Some(1)
+    );
   }
 
   #[test]
@@ -582,9 +945,402 @@ mod tests {
           input: "",
           line: 0,
           col: 13,
+          offset: 13,
+          tab_width: 1,
+          unit: PhantomData,
+        }
+      }
+    );
+  }
+
+  #[test]
+  fn take_while_positions_test() {
+    let parser = parse_while(char::is_alphabetic)
+      .left(parse_spaces())
+      .zip(parse_take(6), |name, taken| (name, taken));
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("foo \n bar\nzoo")),
+      Parser::Parsed {
+        data: ("foo", "bar\nzo"),
+        input: LineBasedStr {
+          input: "o",
+          line: 2,
+          col: 2,
+          offset: 12,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
+
+    let parser = parse_while(|c| c != ';');
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("a\nbc;")),
+      Parser::Parsed {
+        data: "a\nbc",
+        input: LineBasedStr {
+          input: ";",
+          line: 1,
+          col: 2,
+          offset: 4,
+          tab_width: 1,
+          unit: PhantomData,
+        }
+      }
+    );
+  }
+
+  #[test]
+  fn offset_span_test() {
+    fn rest<A, I>(p: Parser<A, I>) -> I {
+      match p {
+        Parser::Parsed { input, .. } => input,
+        _ => panic!("unexpected failure"),
+      }
+    }
+
+    let source = "foo\n  bär baz";
+    let start = rest(parse_while(|c| c != 'b').parse(LineBasedStr::from(source)));
+    let end = rest(parse_take(3).parse(start.clone()));
+    let span = Span::new(Position::of(&start), Position::of(&end));
+
+    assert_eq!(
+      span,
+      Span {
+        start: Position {
+          offset: 6,
+          line: 1,
+          col: 2,
+        },
+        end: Position {
+          offset: 10,
+          line: 1,
+          col: 5,
+        },
+        file: None,
+      }
+    );
+    assert_eq!(span.len(), 4);
+    assert_eq!(span.slice(source), Some("bär"));
+
+    let reversed = Span::new(span.end, span.start);
+    assert_eq!(reversed.len(), 0);
+    assert!(reversed.is_empty());
+    assert_eq!(reversed.slice(source), None);
+
+    let error = parse_take(4)
+      .right(parse_u32())
+      .parse(end)
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.offset(), 14);
+    assert_eq!(error.found, Found::EndOfInput);
+  }
+
+  #[test]
+  fn spanned_test() {
+    let parser = parse_while(char::is_alphabetic)
+      .spanned()
+      .delimited1(parse_lexeme(",").left(parse_spaces()));
+    let source = "foo, \n  bär";
+    let spans = parser.parse(LineBasedStr::from(source)).result().unwrap();
+
+    assert_eq!(
+      spans,
+      vec![
+        Spanned {
+          value: "foo",
+          span: Span::new(
+            Position {
+              offset: 0,
+              line: 0,
+              col: 0,
+            },
+            Position {
+              offset: 3,
+              line: 0,
+              col: 3,
+            }
+          ),
+        },
+        Spanned {
+          value: "bär",
+          span: Span::new(
+            Position {
+              offset: 8,
+              line: 1,
+              col: 2,
+            },
+            Position {
+              offset: 12,
+              line: 1,
+              col: 5,
+            }
+          ),
+        },
+      ]
+    );
+    assert_eq!(spans[1].span.slice(source), Some("bär"));
+
+    let parser = parse_lexeme("(")
+      .right(parse_spaces())
+      .right(parse_u32())
+      .left(parse_spaces())
+      .left(parse_lexeme(")"))
+      .spanned();
+    let spanned = parser
+      .parse(LineBasedStr::from("(\n 42 )!"))
+      .result()
+      .unwrap();
+
+    assert_eq!(spanned.value, 42);
+    assert_eq!(
+      (
+        spanned.span.start.line,
+        spanned.span.end.line,
+        spanned.span.end.col
+      ),
+      (0, 1, 5)
+    );
+    assert_eq!(spanned.span.len(), 7);
+  }
+
+  #[test]
+  fn custom_parser_test() {
+    /// Parenthesized lists of numbers, nested to any depth.
+    struct Nested;
+
+    impl<'a> Parse<&'a str> for Nested {
+      type Output = Vec<u32>;
+
+      fn parse(&self, input: &'a str) -> Parser<Vec<u32>, &'a str> {
+        let item = parse_u32().map(|n| vec![n]).or(Nested);
+
+        parse_lexeme("(")
+          .right(item.delimited0(parse_lexeme(",")))
+          .left(parse_lexeme(")"))
+          .map(|items| items.concat())
+          .parse(input)
+      }
+    }
+
+    type Total = fn(Vec<u32>, Vec<u32>) -> usize;
+
+    /// Parsers can be stored as fields of named types.
+    struct Pair<P> {
+      parser: Zip<P, P, Total>,
+    }
+
+    let pair = Pair {
+      parser: Nested.zip(Nested, |a, b| a.len() + b.len()),
+    };
+
+    assert_eq!(pair.parser.parse("(1,(2,(3)))(4)").ok(), Some(4));
+    assert_eq!(
+      Nested.many1().parse("(1)(2,3)").ok(),
+      Some(vec![vec![1], vec![2, 3]])
+    );
+    assert_eq!(
+      Nested.parse("(1,(2)").result().unwrap_err().to_string(),
+      "expected `)`, found end of input"
+    );
+
+    // closures are parsers too
+    let numbers = parse_u32()
+      .left(|input| parse_lexeme(",").parse(input))
+      .many0();
+
+    assert_eq!(numbers.parse("1,2,").ok(), Some(vec![1, 2]));
+  }
+
+  #[test]
+  fn seq_alt_test() {
+    let word = || parse_while(char::is_alphabetic);
+    let binding = seq((
+      parse_lexeme("let"),
+      parse_spaces(),
+      word(),
+      parse_lexeme(" = "),
+      alt((
+        parse_u32().map(|n| n.to_string()),
+        word().map(str::to_uppercase),
+        |input| parse_lexeme("_").const_map(String::new()).parse(input),
+      )),
+      parse_lexeme(";"),
+    ));
+
+    assert_eq!(
+      binding.parse("let  x = foo;").ok(),
+      Some(((), (), "x", (), "FOO".to_owned(), ()))
+    );
+    assert_eq!(
+      binding
+        .parse("let x = 42;")
+        .ok()
+        .map(|(_, _, name, _, value, _)| (name, value)),
+      Some(("x", "42".to_owned()))
+    );
+    assert_eq!(
+      binding
+        .parse("let x = ?;")
+        .result()
+        .unwrap_err()
+        .to_string(),
+      "expected one of: unsigned integer, matching character, `_`; found `?`"
+    );
+    assert_eq!(seq((parse_u32(),)).parse("7").ok(), Some((7,)));
+  }
+
+  #[test]
+  fn operators_test() {
+    let ident = || parse_while(char::is_alphabetic);
+    let name = || !(parse_lexeme("if") | parse_lexeme("else")) >> ident();
+    let call = name() + (parse_lexeme("(") >> name().opt() << parse_lexeme(")"));
+
+    assert_eq!(call.parse("foo(bar)").ok(), Some(("foo", Some("bar"))));
+    assert_eq!(call.parse("foo()").ok(), Some(("foo", None)));
+    assert_eq!(
+      call.parse("if(x)").result().unwrap_err().to_string(),
+      "expected something else, found `i`"
+    );
+    assert_eq!(
+      (parse_u32() + parse_lexeme(",") + parse_u32())
+        .parse("1,2")
+        .ok(),
+      Some((1, (), 2))
+    );
+    assert_eq!(parse_lexeme("a").not_ahead().parse("b").ok(), Some(()));
+  }
+
+  #[test]
+  fn recursive_test() {
+    // sums of numbers and parenthesized sums
+    let sum = recursive(|sum| {
+      parse_u32()
+        .or(parse_lexeme("(").right(sum).left(parse_lexeme(")")))
+        .delimited1(parse_lexeme("+"))
+        .map(|terms| terms.into_iter().sum::<u32>())
+    });
+
+    assert_eq!(sum.parse("1+(2+(3+4))+5").ok(), Some(15));
+    assert_eq!(
+      sum.clone().many1().parse("(1)(((2)))").ok(),
+      Some(vec![1, 2])
+    );
+    assert_eq!(
+      sum.parse("1+(2+").result().unwrap_err().to_string(),
+      "expected one of: unsigned integer, `(`; found end of input"
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "recursive parser used before being defined")]
+  fn recursive_undefined_test() {
+    recursive(|p: Recursive<(), &str>| {
+      let _ = p.parse("");
+      p
+    });
+  }
+
+  #[test]
+  fn boxed_test() {
+    // keywords and operators as read from a configuration
+    let keywords = ["let".to_owned(), "in".to_owned()];
+    let operators = ["+", "-", "*"];
+
+    let mut tokens: Vec<BoxedParser<String, LineBasedStr>> = keywords
+      .iter()
+      .map(|kw| parse_lexeme(kw).const_map(kw.clone()).boxed())
+      .collect();
+    tokens.extend(
+      operators
+        .iter()
+        .map(|op| parse_lexeme(op).const_map(op.to_string()).boxed()),
+    );
+    tokens.push(parse_u32().map(|n| n.to_string()).boxed());
+
+    let token = choice(tokens).shared();
+    let tokens = token.clone().left(parse_spaces()).many0();
+
+    assert_eq!(
+      tokens.parse(LineBasedStr::from("let 1 - 2 in 3")).ok(),
+      Some(
+        ["let", "1", "-", "2", "in", "3"]
+          .iter()
+          .map(|s| s.to_string())
+          .collect()
+      )
+    );
+    assert_eq!(
+      token.parse(LineBasedStr::from("*")).ok(),
+      Some("*".to_owned())
+    );
+    assert_eq!(
+      choice(vec![parse_lexeme("+").boxed(), parse_lexeme("*").boxed()])
+        .parse(LineBasedStr::from("/"))
+        .result()
+        .unwrap_err()
+        .to_string(),
+      "expected one of: `+`, `*`; found `/`"
+    );
+    assert_eq!(
+      choice(Vec::<BoxedParser<(), _>>::new())
+        .parse(LineBasedStr::from("x"))
+        .ok(),
+      None
+    );
+  }
+
+  #[test]
+  fn recognize_test() {
+    fn number<'a, I>() -> impl Parse<I, Output = &'a str>
+    where
+      I: 'a + Clone + SliceBased<'a> + LineBased,
+    {
+      let digits = || parse_while(|c| c.is_ascii_digit());
+
+      parse_lexeme("-")
+        .opt()
+        .right(digits())
+        .right(parse_lexeme(".").right(digits()).opt())
+        .recognize()
+    }
+
+    assert_eq!(
+      number().parse("-12.5e3"),
+      Parser::Parsed {
+        data: "-12.5",
+        input: "e3",
+      }
+    );
+
+    assert_eq!(
+      number().parse(LineBasedStr::from("3.14159 ")),
+      Parser::Parsed {
+        data: "3.14159",
+        input: LineBasedStr {
+          input: " ",
+          line: 0,
+          col: 7,
+          offset: 7,
+          tab_width: 1,
+          unit: PhantomData,
+        }
+      }
+    );
+
+    let parser = parse_while(|c| c != ';')
+      .left(parse_lexeme(";"))
+      .recognize()
+      .many1();
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("a;\nb;é;")).result(),
+      Ok(vec!["a;", "\nb;", "é;"])
+    );
   }
 
   #[test]
@@ -599,107 +1355,655 @@ mod tests {
           input: "barzoo",
           line: 0,
           col: 3,
+          offset: 3,
+          tab_width: 1,
+          unit: PhantomData,
+        }
+      }
+    );
+
+    // lexemes spanning several lines move the input to the line they end on
+    match parse_lexeme("a\nb").parse(LineBasedStr::from("a\nbc")) {
+      Parser::Parsed { input, .. } => assert_eq!((input.line, input.col), (1, 1)),
+      p => panic!("unexpected failure: {:?}", p),
+    }
+  }
+
+  #[test]
+  fn lexeme_error_test() {
+    let parser = parse_lexeme("foo");
+    let error = parser
+      .parse(LineBasedStr::from("bar"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.expected, vec![Expected::lexeme("foo")]);
+    assert_eq!(error.found, Found::Char('b'));
+    assert_eq!(error.to_string(), "expected `foo`, found `b`");
+  }
+
+  #[test]
+  fn error_propagation_test() {
+    let parser = parse_u32()
+      .left(parse_spaces())
+      .and_then(|a| parse_lexeme("+").right(parse_spaces()).const_map(a))
+      .zip(parse_u32(), |a, b| a + b);
+
+    assert_eq!(parser.parse(LineBasedStr::from("1 + 2")).result(), Ok(3));
+
+    let error = parser
+      .parse(LineBasedStr::from("1 +\n  x"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!((error.line(), error.col()), (1, 2));
+    assert_eq!(error.expected, vec![Expected::named("unsigned integer")]);
+    assert_eq!(error.found, Found::Char('x'));
+
+    let error = parser
+      .parse(LineBasedStr::from("1 - 2"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!((error.line(), error.col()), (0, 2));
+    assert_eq!(error.to_string(), "expected `+`, found `-`");
+  }
+
+  #[test]
+  fn delimited_error_test() {
+    let parser = parse_u32().delimited1(parse_lexeme(","));
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("1,2,3")).result(),
+      Ok(vec![1, 2, 3])
+    );
+
+    let error = parser
+      .parse(LineBasedStr::from("1,2,"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 4);
+    assert_eq!(error.found, Found::EndOfInput);
+  }
+
+  #[test]
+  fn or_error_test() {
+    let parser = parse_u32()
+      .const_map(())
+      .or(parse_lexeme("true"))
+      .or(parse_lexeme("false"))
+      .or(parse_lexeme("true"));
+    let error = parser
+      .parse(LineBasedStr::from("nope"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(
+      error.expected,
+      vec![
+        Expected::named("unsigned integer"),
+        Expected::lexeme("true"),
+        Expected::lexeme("false"),
+      ]
+    );
+    assert_eq!(
+      error.to_string(),
+      "expected one of: unsigned integer, `true`, `false`; found `n`"
+    );
+  }
+
+  #[test]
+  fn or_furthest_error_test() {
+    let parser = parse_lexeme("[")
+      .right(parse_u32())
+      .left(parse_lexeme("]"))
+      .or(parse_u32());
+    let error = parser
+      .parse(LineBasedStr::from("[1)"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 2);
+    assert_eq!(error.to_string(), "expected `]`, found `)`");
+  }
+
+  #[test]
+  fn label_test() {
+    let pair_parser = parse_u32()
+      .left(parse_lexeme(":"))
+      .zip(parse_u32(), |a, b| (a, b))
+      .label("pair");
+    let parser = parse_lexeme("[")
+      .right(pair_parser.delimited0(parse_lexeme(",")))
+      .left(parse_lexeme("]"))
+      .context("list");
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("[1:2,3:4]")).result(),
+      Ok(vec![(1, 2), (3, 4)])
+    );
+
+    let error = parser
+      .parse(LineBasedStr::from("[1:2,x]"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 5);
+    assert_eq!(error.to_string(), "expected pair, found `x`, in list");
+
+    let error = parser
+      .parse(LineBasedStr::from("[1:2,3;4]"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 6);
+    assert_eq!(error.context, vec!["pair".to_owned(), "list".to_owned()]);
+    assert_eq!(
+      error.to_string(),
+      "expected `:`, found `;`, in pair, in list"
+    );
+  }
+
+  #[test]
+  fn context_test() {
+    let list = |name| {
+      parse_lexeme("[")
+        .right(parse_u32().delimited0(parse_lexeme(",")))
+        .left(parse_lexeme("]"))
+        .context(name)
+    };
+    let parser = list("list")
+      .or(list("array"))
+      .or(parse_u32().const_map(vec![]));
+
+    // alternatives failing right away don’t add their context
+    let error = parser.parse(LineBasedStr::from("x")).result().unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      "expected one of: `[`, unsigned integer; found `x`"
+    );
+
+    // contexts of alternatives failing at the same place are merged
+    let error = parser
+      .parse(LineBasedStr::from("[1;"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      "expected `]`, found `;`, in list, in array"
+    );
+  }
+
+  #[test]
+  fn cut_test() {
+    let list_parser = parse_lexeme("[")
+      .right(parse_u32().delimited0(parse_lexeme(",")).cut())
+      .left(parse_lexeme("]").cut());
+    let parser = list_parser.or(parse_u32().map(|n| vec![n]));
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("[1,2]")).result(),
+      Ok(vec![1, 2])
+    );
+    assert_eq!(parser.parse(LineBasedStr::from("3")).result(), Ok(vec![3]));
+
+    match parser.parse(LineBasedStr::from("[1,2;")) {
+      Parser::Fatal { error } => {
+        assert_eq!(error.col(), 4);
+        assert_eq!(error.to_string(), "expected `]`, found `;`");
+      }
+      p => panic!("expected a fatal error, got {:?}", p),
+    }
+  }
+
+  #[test]
+  fn cut_many0_test() {
+    let parser = parse_lexeme("(")
+      .right(parse_u32().cut())
+      .left(parse_lexeme(")").cut())
+      .many0();
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("(1)(2)x")).result(),
+      Ok(vec![1, 2])
+    );
+
+    match parser.parse(LineBasedStr::from("(1)(x)")) {
+      Parser::Fatal { error } => assert_eq!(error.col(), 4),
+      p => panic!("expected a fatal error, got {:?}", p),
+    }
+
+    match parser.opt().parse(LineBasedStr::from("(1)(x)")) {
+      Parser::Fatal { error } => assert_eq!(error.col(), 4),
+      p => panic!("expected a fatal error, got {:?}", p),
+    }
+  }
+
+  #[test]
+  fn cut_delimited0_test() {
+    let parser = parse_lexeme("(")
+      .right(parse_u32().cut())
+      .left(parse_lexeme(")").cut())
+      .delimited0(parse_lexeme(","));
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("(1),(2)")).result(),
+      Ok(vec![1, 2])
+    );
+
+    match parser.parse(LineBasedStr::from("(1),(2,(3)")) {
+      Parser::Fatal { error } => {
+        assert_eq!(error.col(), 6);
+        assert_eq!(error.to_string(), "expected `)`, found `,`");
+      }
+      p => panic!("expected a fatal error, got {:?}", p),
+    }
+  }
+
+  #[test]
+  fn recover_with_test() {
+    let el_parser = parse_u32().recover_with(parse_lexeme(",").or(parse_lexeme("]")), |_| 0);
+    let parser = parse_lexeme("[")
+      .right(el_parser.delimited0(parse_lexeme(",").left(parse_spaces())))
+      .left(parse_lexeme("]"));
+
+    let recovered = parser
+      .parse(Recoverable::from("[1, x, 3, \nyz]"))
+      .recovered();
+
+    assert_eq!(recovered.data, Some(vec![1, 0, 3, 0]));
+    assert_eq!(recovered.errors.len(), 2);
+    assert_eq!(
+      (recovered.errors[0].line(), recovered.errors[0].col()),
+      (0, 4)
+    );
+    assert_eq!(
+      (recovered.errors[1].line(), recovered.errors[1].col()),
+      (1, 0)
+    );
+    assert_eq!(
+      recovered.errors[1].to_string(),
+      "expected unsigned integer, found `y`"
+    );
+
+    let recovered = parser.parse(Recoverable::from("[1, 2")).recovered();
+
+    assert_eq!(recovered.data, None);
+    assert_eq!(recovered.errors.len(), 1);
+    assert_eq!(recovered.errors[0].found, Found::EndOfInput);
+  }
+
+  #[test]
+  fn recover_with_backtrack_test() {
+    // errors recovered from in a branch that gets backtracked from are dropped
+    let parser = parse_u32()
+      .recover_with(parse_lexeme(";"), |_| 0)
+      .left(parse_lexeme(";"))
+      .left(parse_lexeme("!"))
+      .or(parse_lexeme("x;").const_map(1));
+
+    let recovered = parser.parse(Recoverable::from("x;")).recovered();
+
+    assert_eq!(recovered.data, Some(1));
+    assert!(recovered.errors.is_empty());
+  }
+
+  #[test]
+  fn recover_with_fatal_test() {
+    let parser = parse_lexeme("(")
+      .right(parse_u32().cut())
+      .recover_with(parse_lexeme(";"), |_| 0);
+
+    match parser.parse(Recoverable::from("(x;")) {
+      Parser::Fatal { error } => {
+        assert_eq!(error.col(), 1);
+        assert!(error.input.errors().is_empty());
+      }
+      p => panic!("expected a fatal error, got {:?}", p),
+    }
+  }
+
+  #[test]
+  fn partial_test() {
+    let parser = parse_u32().left(parse_lexeme(";"));
+
+    match parser.parse(Partial::new("12")) {
+      Parser::Incomplete { error, needed } => {
+        assert_eq!(needed, 1);
+        assert_eq!(error.expected, vec![Expected::named("unsigned integer")]);
+        assert_eq!(error.found, Found::EndOfInput);
+      }
+      p => panic!("expected an incomplete input, got {:?}", p),
+    }
+
+    match parser.parse(Partial::new("12")).result() {
+      Err(error) => assert_eq!(error.input, Partial::new("12")),
+      Ok(data) => panic!("expected an error, got {:?}", data),
+    }
+
+    assert_eq!(parser.parse(Partial::new("12;")).ok(), Some(12));
+    assert_eq!(
+      parser
+        .parse(Partial::new("12").complete())
+        .result()
+        .unwrap_err()
+        .to_string(),
+      "expected `;`, found end of input"
+    );
+
+    let booleans = parse_lexeme("true")
+      .or(parse_lexeme("false"))
+      .left(parse_spaces())
+      .many0();
+
+    assert!(matches!(
+      booleans.parse(Partial::new("true fa")),
+      Parser::Incomplete { needed: 3, .. }
+    ));
+    assert!(matches!(
+      booleans.parse(Partial::new("true ")),
+      Parser::Incomplete { needed: 1, .. }
+    ));
+    assert!(matches!(
+      booleans.parse(Partial::new("true nope")),
+      Parser::Parsed { .. }
+    ));
+    assert!(matches!(
+      parse_take(4).parse(Partial::new("ab")),
+      Parser::Incomplete { needed: 2, .. }
+    ));
+    assert!(matches!(
+      parse_while(char::is_alphabetic).parse(Partial::new("ab")),
+      Parser::Incomplete { needed: 1, .. }
+    ));
+  }
+
+  #[test]
+  fn partial_resume_test() {
+    let mut buffer = String::new();
+    let mut chunks = vec!["(1", "2,", "3", "4)", "rest"].into_iter();
+
+    let data = loop {
+      let parsed = parse_lexeme("(")
+        .right(parse_u32().delimited0(parse_lexeme(",")))
+        .left(parse_lexeme(")"))
+        .parse(Partial::new(buffer.as_str()));
+
+      match parsed {
+        Parser::Incomplete { .. } => (),
+        p => break p.ok(),
+      }
+
+      buffer.push_str(chunks.next().unwrap());
+    };
+
+    assert_eq!(data, Some(vec![12, 34]));
+    assert_eq!(chunks.next(), Some("rest"));
+  }
+
+  #[test]
+  fn or_test() {
+    let parser = parse_lexeme("foo").or(parse_lexeme("bar"));
+
+    assert_eq!(
+      parser.parse("foo"),
+      Parser::Parsed {
+        data: (),
+        input: "",
+      }
+    );
+
+    assert_eq!(
+      parser.parse("bar"),
+      Parser::Parsed {
+        data: (),
+        input: "",
+      }
+    );
+
+    let parser = parser.many1();
+
+    assert_eq!(
+      parser.parse("foobar"),
+      Parser::Parsed {
+        data: vec![(), ()],
+        input: "",
+      }
+    );
+  }
+
+  #[test]
+  fn zip_test() {
+    let parser = parse_u32().zip(parse_take(3), |n, l| (n, l));
+
+    assert_eq!(
+      parser.parse("123foolol"),
+      Parser::Parsed {
+        data: (123, "foo"),
+        input: "lol",
+      }
+    );
+  }
+
+  #[test]
+  fn left_test() {
+    let parser = parse_u32().left(parse_spaces());
+
+    assert_eq!(
+      parser.parse("123  lol"),
+      Parser::Parsed {
+        data: 123,
+        input: "lol",
+      }
+    );
+  }
+
+  #[test]
+  fn right_test() {
+    let parser = parse_spaces().right(parse_u32());
+
+    assert_eq!(
+      parser.parse("   123lol"),
+      Parser::Parsed {
+        data: 123,
+        input: "lol",
+      }
+    );
+  }
+
+  #[test]
+  fn applicative_person_test() {
+    #[derive(Debug, Eq, PartialEq)]
+    struct Person {
+      name: String,
+      age: u32,
+    }
+
+    let parser =
+      parse_while(char::is_alphabetic)
+        .left(parse_spaces())
+        .zip(parse_u32(), |name, age| Person {
+          name: name.to_owned(),
+          age,
+        });
+
+    let expected = Person {
+      name: "Henry".to_owned(),
+      age: 48,
+    };
+    assert_eq!(
+      parser.parse("Henry 48lol"),
+      Parser::Parsed {
+        data: expected,
+        input: "lol",
+      }
+    );
+  }
+
+  /// Line and column reached after going through a string from the start of the input.
+  fn position_after(s: &str) -> (usize, usize) {
+    s.chars().fold((0, 0), |(line, col), c| match c {
+      '\n' => (line + 1, 0),
+      _ => (line, col + 1),
+    })
+  }
+
+  /// Text generated by property tests; printable characters along with newlines and tabs.
+  const TEXT: &str = "(\\PC|\n|\t)*";
+
+  #[test]
+  fn utf8_test() {
+    let parser = parse_while(|c| c != ' ')
+      .left(parse_lexeme(" → "))
+      .zip(parse_take(2), |a, b| (a, b));
+
+    assert_eq!(
+      parser.parse(LineBasedStr::from("héhé → 日本語")),
+      Parser::Parsed {
+        data: ("héhé", "日本"),
+        input: LineBasedStr {
+          input: "語",
+          line: 0,
+          col: 9,
+          offset: 17,
+          tab_width: 1,
+          unit: PhantomData,
         }
       }
     );
   }
 
-  // #[test]
-  // fn or_test() {
-  //   let parser = parse_lexeme("foo").or(parse_lexeme("bar"));
-
-  //   assert_eq!(
-  //     parser.parse("foo"),
-  //     Parser::Parsed {
-  //       data: (),
-  //       input: "",
-  //     }
-  //   );
-
-  //   assert_eq!(
-  //     parser.parse("bar"),
-  //     Parser::Parsed {
-  //       data: (),
-  //       input: "",
-  //     }
-  //   );
-
-  //   let parser = parser.many1();
-
-  //   assert_eq!(
-  //     parser.parse("foobar"),
-  //     Parser::Parsed {
-  //       data: vec![(), ()],
-  //       input: "",
-  //     }
-  //   );
-  // }
-
-  // #[test]
-  // fn zip_test() {
-  //   let parser = parse_u32().zip(parse_take(3), |n, l| (n, l));
-
-  //   assert_eq!(
-  //     parser.parse("123foolol"),
-  //     Parser::Parsed {
-  //       data: (123, "foo"),
-  //       input: "lol",
-  //     }
-  //   );
-  // }
-
-  // #[test]
-  // fn left_test() {
-  //   let parser = parse_u32().left(parse_spaces());
-
-  //   assert_eq!(
-  //     parser.parse("123  lol"),
-  //     Parser::Parsed {
-  //       data: 123,
-  //       input: "lol",
-  //     }
-  //   );
-  // }
-
-  // #[test]
-  // fn right_test() {
-  //   let parser = parse_spaces().right(parse_u32());
-
-  //   assert_eq!(
-  //     parser.parse("   123lol"),
-  //     Parser::Parsed {
-  //       data: 123,
-  //       input: "lol",
-  //     }
-  //   );
-  // }
-
-  // #[test]
-  // fn applicative_person_test() {
-  //   #[derive(Debug, Eq, PartialEq)]
-  //   struct Person {
-  //     name: String,
-  //     age: u32,
-  //   }
-
-  //   let parser =
-  //     parse_while(char::is_alphabetic)
-  //       .left(parse_spaces())
-  //       .zip(parse_u32(), |name, age| Person {
-  //         name: name.to_owned(),
-  //         age,
-  //       });
-
-  //   let expected = Person {
-  //     name: "Henry".to_owned(),
-  //     age: 48,
-  //   };
-  //   assert_eq!(
-  //     parser.parse("Henry 48lol"),
-  //     Parser::Parsed {
-  //       data: expected,
-  //       input: "lol",
-  //     }
-  //   );
-  // }
+  #[test]
+  fn column_units_test() {
+    fn position<U>() -> (usize, usize)
+    where
+      U: ColumnUnit + PartialEq,
+    {
+      let parser = parse_while(|c| c != '=')
+        .left(parse_lexeme("="))
+        .left(parse_spaces())
+        .left(parse_lexeme("e\u{301}👨‍👩‍👧"));
+
+      match parser.parse(LineBasedStr::<U>::new("a\u{301}b =\n  e\u{301}👨‍👩‍👧!")) {
+        Parser::Parsed { input, .. } => (input.line, input.col),
+        p => panic!(
+          "unexpected failure: {:?}",
+          p.result().err().map(|e| e.to_string())
+        ),
+      }
+    }
+
+    assert_eq!(position::<Bytes>(), (1, 23));
+    assert_eq!(position::<Chars>(), (1, 9));
+    assert_eq!(position::<Utf16>(), (1, 12));
+    #[cfg(feature = "graphemes")]
+    assert_eq!(position::<Graphemes>(), (1, 4));
+
+    let error = parse_while(char::is_alphabetic)
+      .right(parse_u32())
+      .parse(LineBasedStr::<Utf16>::new("𝔘𝔫𝔦!"))
+      .result()
+      .unwrap_err();
+
+    assert_eq!(error.col(), 6);
+  }
+
+  #[cfg(feature = "graphemes")]
+  #[test]
+  fn split_grapheme_test() {
+    // clusters split across two parsers count as a single column
+    let parser = parse_lexeme("e")
+      .right(parse_lexeme("\u{301}"))
+      .right(parse_lexeme("👨\u{200d}"))
+      .right(parse_lexeme("👩\u{200d}👧"))
+      .right(parse_take(2));
+
+    match parser.parse(LineBasedStr::<Graphemes>::new(
+      "e\u{301}👨\u{200d}👩\u{200d}👧\tx\u{301}!",
+    )) {
+      Parser::Parsed { data, input } => {
+        assert_eq!(data, "\tx");
+        assert_eq!(input.col, 3);
+      }
+      p => panic!("unexpected failure: {:?}", p),
+    }
+
+    match parser.parse(
+      LineBasedStr::<Graphemes>::new("e\u{301}👨\u{200d}👩\u{200d}👧\tx\u{301}!").with_tab_width(4),
+    ) {
+      Parser::Parsed { input, .. } => assert_eq!(input.col, 4),
+      p => panic!("unexpected failure: {:?}", p),
+    }
+  }
+
+  proptest! {
+    #[test]
+    fn parse_while_prop(s in TEXT, which in 0..3usize) {
+      let predicates: [fn(char) -> bool; 3] = [char::is_alphabetic, char::is_whitespace, |c| !c.is_ascii()];
+      let predicate = predicates[which];
+
+      match parse_while(predicate).parse(LineBasedStr::from(s.as_str())) {
+        Parser::Parsed { data, input } => {
+          prop_assert!(!data.is_empty());
+          prop_assert!(data.chars().all(predicate));
+          prop_assert_eq!(format!("{}{}", data, input.input), s.clone());
+          prop_assert!(!input.input.chars().next().is_some_and(predicate));
+          prop_assert_eq!((input.line, input.col), position_after(data));
+        }
+
+        p => prop_assert!(!s.chars().next().is_some_and(predicate), "{:?}", p),
+      }
+    }
+
+    #[test]
+    fn parse_take_prop(s in TEXT, count in 0..16usize) {
+      match parse_take(count).parse(LineBasedStr::from(s.as_str())) {
+        Parser::Parsed { data, input } => {
+          prop_assert_eq!(data.chars().count(), count);
+          prop_assert_eq!(format!("{}{}", data, input.input), s.clone());
+          prop_assert_eq!((input.line, input.col), position_after(data));
+        }
+
+        _ => prop_assert!(s.chars().count() < count),
+      }
+    }
+
+    #[test]
+    fn parse_lexeme_prop(lexeme in "(\\PC|\n|\t)+", rest in TEXT) {
+      let s = format!("{}{}", lexeme, rest);
+      let (line, col) = position_after(&lexeme);
+
+      prop_assert_eq!(
+        parse_lexeme(lexeme.as_str()).parse(LineBasedStr::from(s.as_str())),
+        Parser::Parsed {
+          data: (),
+          input: LineBasedStr {
+            input: rest.as_str(),
+            line,
+            col,
+            offset: lexeme.len(),
+            tab_width: 1,
+            unit: PhantomData,
+          }
+        }
+      );
+    }
+
+    #[test]
+    fn primitives_no_panic_prop(s in TEXT) {
+      let parser = parse_spaces()
+        .right(parse_u32().const_map(()).or(parse_lexeme("é")))
+        .many0();
+
+      if let Parser::Parsed { data, input } = parser.parse(LineBasedStr::from(s.as_str())) {
+        let consumed = &s[..s.len() - input.input.len()];
+        prop_assert!(data.len() <= consumed.len());
+        prop_assert_eq!(input.offset, consumed.len());
+        prop_assert_eq!((input.line, input.col), position_after(consumed));
+      }
+    }
+  }
 }
@@ -0,0 +1,601 @@
+use crate::{
+  error::{Expected, ParseError},
+  input::{Chars, ColumnUnit, LineBasedStr, Partial, Position},
+  Parse, Parser,
+};
+use std::{
+  cell::OnceCell,
+  error::Error,
+  fmt,
+  io::{self, Read},
+  marker::PhantomData,
+  str,
+};
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Adapter feeding parsers with text read from a [`Read`], one value at a time.
+///
+/// Text is read in chunks into a buffer that only holds what is left to parse, so that the whole input
+/// never has to be in memory. Until the reader is exhausted, parsers see the buffer as a [`Partial`]
+/// input: when they need more than what was read so far, more is read and they run again from the start
+/// of the value. Reads double in size while a value needs more input, so that a value is parsed again
+/// a logarithmic number of times, as long as the reader fills the reads it is asked for.
+///
+/// Columns are counted in the unit `U`; see [`LineBasedStr`]. Positions carry on from one value to the
+/// next.
+///
+/// Parsers given to [`Reader::parse`] must accept inputs of any lifetime. Grammars built once for a
+/// given input type, such as recursive or boxed ones, are given to the [`LendingReader`] made by
+/// [`Reader::lend`] instead.
+#[derive(Debug)]
+pub struct Reader<R, U = Chars> {
+  reader: R,
+  buffer: Vec<u8>,
+  /// Length of the start of the buffer known to be valid UTF-8.
+  valid: usize,
+  chunk_size: usize,
+  tab_width: usize,
+  position: Position,
+  eof: bool,
+  unit: PhantomData<U>,
+}
+
+impl<R, U> Reader<R, U>
+where
+  R: Read,
+  U: ColumnUnit + PartialEq,
+{
+  pub fn new(reader: R) -> Self {
+    Reader {
+      reader,
+      buffer: Vec::new(),
+      valid: 0,
+      chunk_size: DEFAULT_CHUNK_SIZE,
+      tab_width: 1,
+      position: Position::default(),
+      eof: false,
+      unit: PhantomData,
+    }
+  }
+
+  /// Number of bytes to read at first for each value; 8 KiB by default.
+  pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+    Self {
+      chunk_size: chunk_size.max(1),
+      ..self
+    }
+  }
+
+  /// Width between two tab stops of the inputs handed to parsers; see [`LineBasedStr::with_tab_width`].
+  pub fn with_tab_width(self, tab_width: usize) -> Self {
+    Self { tab_width, ..self }
+  }
+
+  /// Position the next value will be parsed from.
+  pub fn position(&self) -> Position {
+    self.position
+  }
+
+  /// Parse the next value, reading as much as the parser needs.
+  ///
+  /// The buffer the inputs borrow changes as it is refilled, so the parser must accept inputs of any
+  /// lifetime, such as a function taking an input. It can be built once and handed to every call.
+  ///
+  /// Returns `None` once the reader is exhausted and everything it produced was consumed. Parsers
+  /// succeeding without consuming anything would never get there, so they fail instead.
+  pub fn parse<P, A>(&mut self, parser: &P) -> Result<Option<A>, ReadError>
+  where
+    P: for<'b> Parse<Partial<LineBasedStr<'b, U>>, Output = A>,
+  {
+    let mut read_size = self.chunk_size;
+
+    loop {
+      if self.buffer.is_empty() {
+        if self.eof {
+          return Ok(None);
+        }
+
+        self.fill(read_size)?;
+        continue;
+      }
+
+      let invalid = self.validate();
+      let text = str::from_utf8(&self.buffer[..self.valid]).expect("valid UTF-8 prefix");
+
+      match self.step(parser, text, invalid)? {
+        Step::Parsed(data, position) => {
+          let consumed = position.offset - self.position.offset;
+          self.buffer.drain(..consumed);
+          self.valid -= consumed;
+          self.position = position;
+          return Ok(Some(data));
+        }
+
+        Step::Incomplete(needed) => {
+          self.fill(read_size.max(needed))?;
+          read_size = read_size.saturating_mul(2);
+        }
+      }
+    }
+  }
+
+  /// Lend what is read from now on to parsers, for as long as `buffer` lives.
+  ///
+  /// What was read but not parsed yet is lent as well.
+  pub fn lend<'b>(self, buffer: &'b ReadBuffer) -> LendingReader<'b, R, U> {
+    LendingReader {
+      reader: self,
+      tail: &buffer.head,
+      text: "",
+    }
+  }
+
+  /// Check that what was read since the last check is valid UTF-8, telling whether the buffer ends with
+  /// bytes that won’t turn into text, whatever is read next.
+  fn validate(&mut self) -> bool {
+    let error_len = match str::from_utf8(&self.buffer[self.valid..]) {
+      Ok(_) => {
+        self.valid = self.buffer.len();
+        None
+      }
+
+      Err(e) => {
+        self.valid += e.valid_up_to();
+        e.error_len()
+      }
+    };
+
+    error_len.is_some() || (self.eof && self.valid < self.buffer.len())
+  }
+
+  /// Run a parser over the text starting at the current position.
+  ///
+  /// `invalid` tells whether bytes that won’t turn into text follow the text.
+  fn step<'b, P>(
+    &self,
+    parser: &P,
+    text: &'b str,
+    invalid: bool,
+  ) -> Result<Step<P::Output>, ReadError>
+  where
+    P: Parse<Partial<LineBasedStr<'b, U>>>,
+  {
+    let input = Partial {
+      input: LineBasedStr {
+        input: text,
+        line: self.position.line,
+        col: self.position.col,
+        offset: self.position.offset,
+        tab_width: self.tab_width,
+        unit: PhantomData,
+      },
+      partial: !self.eof || invalid,
+    };
+
+    let (needed, error) = match parser.parse(input) {
+      Parser::Parsed { data, input } => {
+        if input.input.offset == self.position.offset {
+          let error = ParseError::new(input, Expected::named("non-empty match"));
+          return Err(ReadError::Parse(
+            error.map_input(|input| Position::of(&input)),
+          ));
+        }
+
+        return Ok(Step::Parsed(data, Position::of(&input)));
+      }
+
+      Parser::NoParse { error } | Parser::Fatal { error } => (None, error),
+      Parser::Incomplete { error, needed } => (Some(needed), error),
+    };
+
+    match needed {
+      Some(_) if invalid => Err(ReadError::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+      ))),
+
+      Some(needed) if !self.eof => Ok(Step::Incomplete(needed)),
+      _ => Err(ReadError::Parse(
+        error.map_input(|input| Position::of(&input)),
+      )),
+    }
+  }
+
+  /// Read up to `size` more bytes at the end of the buffer.
+  fn fill(&mut self, size: usize) -> io::Result<()> {
+    let len = self.buffer.len();
+    self.buffer.resize(len + size, 0);
+
+    let read = loop {
+      match self.reader.read(&mut self.buffer[len..]) {
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        read => break read,
+      }
+    };
+
+    self
+      .buffer
+      .truncate(len + read.as_ref().map_or(0, |count| *count));
+    self.eof = read? == 0;
+
+    Ok(())
+  }
+}
+
+/// Outcome of a parser run by a reader, short of failing.
+enum Step<A> {
+  /// Value parsed and position right after it.
+  Parsed(A, Position),
+  /// More input is needed, at least this many bytes.
+  Incomplete(usize),
+}
+
+/// Storage of the text a [`LendingReader`] lends to parsers.
+///
+/// Text is kept until the buffer is dropped, so that parsers can borrow it for as long as the buffer
+/// lives, rather than only during a call as with [`Reader::parse`]. The whole input thus ends up in
+/// memory, and more: each time a value needs more input, what is left of it is copied along with what
+/// is read next.
+#[derive(Debug, Default)]
+pub struct ReadBuffer {
+  head: OnceCell<Box<Chunk>>,
+}
+
+impl ReadBuffer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl Drop for ReadBuffer {
+  fn drop(&mut self) {
+    // drop chunks one after the other rather than recursively, which might overflow the stack
+    let mut next = self.head.take();
+
+    while let Some(mut chunk) = next {
+      next = chunk.next.take();
+    }
+  }
+}
+
+/// Text lent at once, followed by the text lent after it.
+#[derive(Debug)]
+struct Chunk {
+  text: Box<str>,
+  next: OnceCell<Box<Chunk>>,
+}
+
+/// [`Reader`] lending the text it reads to parsers for as long as its [`ReadBuffer`] lives; see
+/// [`Reader::lend`].
+///
+/// Inputs all have the lifetime of the buffer, so that a grammar built once for them, such as a
+/// [`Recursive`] or a [`BoxedParser`], parses every value, and that values can borrow the text they
+/// were parsed from.
+///
+/// [`Recursive`]: crate::combinator::Recursive
+/// [`BoxedParser`]: crate::combinator::BoxedParser
+#[derive(Debug)]
+pub struct LendingReader<'b, R, U = Chars> {
+  reader: Reader<R, U>,
+  /// Where the next chunk of text goes.
+  tail: &'b OnceCell<Box<Chunk>>,
+  /// Text lent but not parsed yet.
+  text: &'b str,
+}
+
+impl<'b, R, U> LendingReader<'b, R, U>
+where
+  R: Read,
+  U: ColumnUnit + PartialEq,
+{
+  /// Position the next value will be parsed from.
+  pub fn position(&self) -> Position {
+    self.reader.position
+  }
+
+  /// Parse the next value, reading as much as the parser needs; see [`Reader::parse`].
+  pub fn parse<P>(&mut self, parser: &P) -> Result<Option<P::Output>, ReadError>
+  where
+    P: Parse<Partial<LineBasedStr<'b, U>>>,
+  {
+    let mut read_size = self.reader.chunk_size;
+
+    loop {
+      if self.text.is_empty() && self.reader.buffer.is_empty() {
+        if self.reader.eof {
+          return Ok(None);
+        }
+
+        self.reader.fill(read_size)?;
+        continue;
+      }
+
+      let invalid = self.reader.validate();
+
+      if self.reader.valid > 0 {
+        self.lend_valid();
+      }
+
+      match self.reader.step(parser, self.text, invalid)? {
+        Step::Parsed(data, position) => {
+          self.text = &self.text[position.offset - self.reader.position.offset..];
+          self.reader.position = position;
+          return Ok(Some(data));
+        }
+
+        Step::Incomplete(needed) => {
+          self.reader.fill(read_size.max(needed))?;
+          read_size = read_size.saturating_mul(2);
+        }
+      }
+    }
+  }
+
+  /// Lend the text read since the last time, along with the text left to parse so that it is
+  /// contiguous.
+  fn lend_valid(&mut self) {
+    let valid = self.reader.valid;
+    let mut text = String::with_capacity(self.text.len() + valid);
+    text.push_str(self.text);
+    text.push_str(str::from_utf8(&self.reader.buffer[..valid]).expect("valid UTF-8 prefix"));
+
+    self.reader.buffer.drain(..valid);
+    self.reader.valid = 0;
+
+    // other readers may have lent text from the same buffer in the meantime
+    while let Some(chunk) = self.tail.get() {
+      self.tail = &chunk.next;
+    }
+
+    let chunk = Box::new(Chunk {
+      text: text.into_boxed_str(),
+      next: OnceCell::new(),
+    });
+    let chunk = self.tail.get_or_init(|| chunk);
+
+    self.tail = &chunk.next;
+    self.text = &chunk.text;
+  }
+}
+
+/// Error of a [`Reader`]: either reading or parsing failed.
+#[derive(Debug)]
+pub enum ReadError {
+  Io(io::Error),
+  /// Parse error, located by the position it occurred at.
+  Parse(ParseError<Position>),
+}
+
+impl From<io::Error> for ReadError {
+  fn from(e: io::Error) -> Self {
+    ReadError::Io(e)
+  }
+}
+
+impl fmt::Display for ReadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ReadError::Io(e) => write!(f, "{}", e),
+      ReadError::Parse(e) => write!(f, "{}:{}: {}", e.input.line + 1, e.input.col + 1, e),
+    }
+  }
+}
+
+impl Error for ReadError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      ReadError::Io(e) => Some(e),
+      ReadError::Parse(e) => Some(e),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    input::Utf16, parse_lexeme, parse_spaces, parse_u32, parse_while, recursive, Parse, ParseExt,
+  };
+  use std::{
+    io::Cursor,
+    sync::atomic::{AtomicUsize, Ordering},
+  };
+
+  fn pair(input: Partial<LineBasedStr>) -> Parser<(u32, u32), Partial<LineBasedStr>> {
+    parse_lexeme("(")
+      .right(parse_u32())
+      .left(parse_lexeme(","))
+      .zip(parse_u32(), |a, b| (a, b))
+      .left(parse_lexeme(")"))
+      .left(parse_spaces())
+      .parse(input)
+  }
+
+  fn word(input: Partial<LineBasedStr>) -> Parser<String, Partial<LineBasedStr>> {
+    parse_while(|c| c != ';')
+      .map(str::to_owned)
+      .left(parse_lexeme(";"))
+      .parse(input)
+  }
+
+  #[test]
+  fn reader_test() {
+    let source = "(1,2)\n(34,5)  (6,789)\n";
+    let mut reader = Reader::new(Cursor::new(source)).with_chunk_size(3);
+    let mut pairs = Vec::new();
+
+    while let Some(p) = reader.parse(&pair).unwrap() {
+      pairs.push(p);
+    }
+
+    assert_eq!(pairs, vec![(1, 2), (34, 5), (6, 789)]);
+    assert_eq!(
+      reader.position(),
+      Position {
+        offset: source.len(),
+        line: 2,
+        col: 0
+      }
+    );
+    assert!(reader.buffer.is_empty());
+  }
+
+  #[test]
+  fn reader_split_char_test() {
+    // 3-byte chunks split the 4-byte characters
+    let source = "𝔘𝔫𝔦;𝔠𝔬𝔡𝔢;";
+    let mut reader = Reader::new(Cursor::new(source)).with_chunk_size(3);
+
+    assert_eq!(reader.parse(&word).unwrap(), Some("𝔘𝔫𝔦".to_owned()));
+    assert_eq!(reader.parse(&word).unwrap(), Some("𝔠𝔬𝔡𝔢".to_owned()));
+    assert_eq!(reader.parse(&word).unwrap(), None);
+    assert_eq!(reader.position().col, 9);
+  }
+
+  #[test]
+  fn reader_error_test() {
+    let mut reader = Reader::new(Cursor::new("(1,2)\n(3;4)")).with_chunk_size(4);
+
+    assert_eq!(reader.parse(&pair).unwrap(), Some((1, 2)));
+
+    match reader.parse(&pair) {
+      Err(ReadError::Parse(error)) => {
+        assert_eq!((error.input.line, error.input.col), (1, 2));
+        assert_eq!(error.to_string(), "expected `,`, found `;`");
+      }
+      r => panic!("expected a parse error, got {:?}", r),
+    }
+
+    let mut reader = Reader::new(Cursor::new("(1,2"));
+
+    match reader.parse(&pair) {
+      Err(e) => assert_eq!(e.to_string(), "1:5: expected `)`, found end of input"),
+      r => panic!("expected a parse error, got {:?}", r),
+    }
+  }
+
+  #[test]
+  fn reader_invalid_utf8_test() {
+    let mut reader = Reader::new(Cursor::new(b"(1,2)(3\xff,4)".to_vec()));
+
+    assert_eq!(reader.parse(&pair).unwrap(), Some((1, 2)));
+
+    match reader.parse(&pair) {
+      Err(ReadError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+      r => panic!("expected an I/O error, got {:?}", r),
+    }
+  }
+
+  #[test]
+  fn reader_growth_test() {
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counted(input: Partial<LineBasedStr>) -> Parser<String, Partial<LineBasedStr>> {
+      ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+      word(input)
+    }
+
+    // reads double while a value needs more input, rather than staying at the chunk size
+    let source = format!("{};", "a".repeat(4096));
+    let mut reader = Reader::new(Cursor::new(source)).with_chunk_size(1);
+
+    assert_eq!(reader.parse(&counted).unwrap(), Some("a".repeat(4096)));
+    assert!(ATTEMPTS.load(Ordering::Relaxed) <= 16);
+  }
+
+  #[test]
+  fn reader_no_progress_test() {
+    fn spaces(input: Partial<LineBasedStr>) -> Parser<(), Partial<LineBasedStr>> {
+      parse_spaces().parse(input)
+    }
+
+    let mut reader = Reader::new(Cursor::new("(1,2)x"));
+
+    assert_eq!(reader.parse(&pair).unwrap(), Some((1, 2)));
+
+    match reader.parse(&spaces) {
+      Err(e) => assert_eq!(e.to_string(), "1:6: expected non-empty match, found `x`"),
+      r => panic!("expected a parse error, got {:?}", r),
+    }
+  }
+
+  #[test]
+  fn reader_unit_test() {
+    fn utf16_word(
+      input: Partial<LineBasedStr<Utf16>>,
+    ) -> Parser<String, Partial<LineBasedStr<Utf16>>> {
+      parse_while(|c| c != ';')
+        .map(str::to_owned)
+        .left(parse_lexeme(";"))
+        .parse(input)
+    }
+
+    let mut reader = Reader::new(Cursor::new("𝔘𝔫𝔦;")).with_chunk_size(3);
+
+    assert_eq!(reader.parse(&utf16_word).unwrap(), Some("𝔘𝔫𝔦".to_owned()));
+    assert_eq!(reader.position().col, 7);
+  }
+
+  #[test]
+  fn lending_reader_recursive_test() {
+    let buffer = ReadBuffer::new();
+    // sums of numbers and parenthesized sums, built once for every value
+    let sum = recursive(|sum| {
+      parse_u32()
+        .or(parse_lexeme("(").right(sum).left(parse_lexeme(")")))
+        .delimited1(parse_lexeme("+"))
+        .map(|terms| terms.into_iter().sum::<u32>())
+    });
+    let statement = sum.left(parse_lexeme(";")).left(parse_spaces());
+
+    let source = "1+2;\n(3+4)+5; ((6));";
+    let mut reader: LendingReader<_> = Reader::new(Cursor::new(source))
+      .with_chunk_size(3)
+      .lend(&buffer);
+    let mut sums = Vec::new();
+
+    while let Some(sum) = reader.parse(&statement).unwrap() {
+      sums.push(sum);
+    }
+
+    assert_eq!(sums, vec![3, 12, 6]);
+    assert_eq!(
+      reader.position(),
+      Position {
+        offset: source.len(),
+        line: 1,
+        col: 15
+      }
+    );
+  }
+
+  #[test]
+  fn lending_reader_boxed_test() {
+    let buffer = ReadBuffer::new();
+    let word = parse_while(|c| c != ';').left(parse_lexeme(";")).boxed();
+
+    // a split character and words borrowing text read before refills
+    let mut reader = Reader::new(Cursor::new("𝔘𝔫𝔦;𝔠𝔬𝔡𝔢;"))
+      .with_chunk_size(3)
+      .lend(&buffer);
+    let mut words = Vec::new();
+
+    while let Some(word) = reader.parse(&word).unwrap() {
+      words.push(word);
+    }
+
+    assert_eq!(words, vec!["𝔘𝔫𝔦", "𝔠𝔬𝔡𝔢"]);
+
+    // text read but not parsed yet is lent too
+    let mut reader = Reader::new(Cursor::new("(1,2)(3,4)"));
+    assert_eq!(reader.parse(&pair).unwrap(), Some((1, 2)));
+
+    let mut reader = reader.lend(&buffer);
+
+    match reader.parse(&word) {
+      Err(e) => assert_eq!(e.to_string(), "1:11: expected `;`, found end of input"),
+      r => panic!("expected a parse error, got {:?}", r),
+    }
+  }
+}
@@ -0,0 +1,153 @@
+use crate::{
+  diagnostic::Diagnostic,
+  error::ParseError,
+  input::{FileId, InFile, LineBasedStr},
+};
+
+/// File registered in a [`SourceMap`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceFile {
+  name: String,
+  source: String,
+}
+
+impl SourceFile {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+}
+
+/// Set of source files, each identified by a [`FileId`].
+///
+/// Inputs created by the source map carry the id of their file, which ends up in the spans of
+/// [`ParseExt::spanned`] and lets [`SourceMap::diagnostic`] name the file an error comes from.
+///
+/// [`ParseExt::spanned`]: crate::ParseExt::spanned
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Register a file, returning its id.
+  pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+    self.files.push(SourceFile {
+      name: name.into(),
+      source: source.into(),
+    });
+
+    FileId(self.files.len() - 1)
+  }
+
+  /// File registered with a given id, if it comes from this source map.
+  pub fn get(&self, file: FileId) -> Option<&SourceFile> {
+    self.files.get(file.0)
+  }
+
+  /// Id of the first file registered with a given name, if any.
+  pub fn find(&self, name: &str) -> Option<FileId> {
+    self.files.iter().position(|f| f.name == name).map(FileId)
+  }
+
+  /// Input over the source of a file, if it comes from this source map.
+  pub fn input(&self, file: FileId) -> Option<InFile<LineBasedStr<'_>>> {
+    let source = self.get(file)?.source();
+    Some(InFile::new(LineBasedStr::new(source), file))
+  }
+
+  /// Diagnostic of an error that occurred in one of the files, named after it; `None` if the file
+  /// doesn’t come from this source map.
+  pub fn diagnostic<'a, U>(
+    &'a self,
+    error: ParseError<InFile<LineBasedStr<'a, U>>>,
+  ) -> Option<Diagnostic<'a, U>> {
+    let file = self.get(error.input.file)?;
+    let diagnostic = Diagnostic::new(file.source(), error.map_input(|input| input.input));
+
+    Some(diagnostic.file_name(file.name()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    input::{Position, Span},
+    parse_lexeme, parse_spaces, parse_while, Parse, ParseExt,
+  };
+
+  #[test]
+  fn source_map_test() {
+    let mut sources = SourceMap::new();
+    let main = sources.add("main.dsl", "include lib.dsl\n");
+    let lib = sources.add("lib.dsl", "foo\n  bar");
+
+    assert_eq!(sources.find("lib.dsl"), Some(lib));
+    assert_eq!(sources.find("nope.dsl"), None);
+    assert_eq!(sources.get(main).map(SourceFile::name), Some("main.dsl"));
+    assert_eq!(SourceMap::new().get(main), None);
+
+    let include = parse_lexeme("include")
+      .right(parse_spaces())
+      .right(parse_while(|c| !c.is_whitespace()));
+    let included = include.parse(sources.input(main).unwrap()).ok();
+
+    assert_eq!(included.and_then(|name| sources.find(name)), Some(lib));
+
+    let words = parse_while(char::is_alphabetic)
+      .spanned()
+      .left(parse_spaces())
+      .many0()
+      .parse(sources.input(lib).unwrap())
+      .ok()
+      .unwrap();
+
+    assert_eq!(
+      words[1].span,
+      Span {
+        start: Position {
+          offset: 6,
+          line: 1,
+          col: 2
+        },
+        end: Position {
+          offset: 9,
+          line: 1,
+          col: 5
+        },
+        file: Some(lib),
+      }
+    );
+  }
+
+  #[test]
+  fn source_map_diagnostic_test() {
+    let mut sources = SourceMap::new();
+    sources.add("main.dsl", "1, 2");
+    let lib = sources.add("lib.dsl", "\n(;");
+
+    let error = parse_spaces()
+      .right(parse_lexeme("("))
+      .right(parse_lexeme(")"))
+      .parse(sources.input(lib).unwrap())
+      .result()
+      .unwrap_err();
+
+    assert_eq!(
+      sources.diagnostic(error).unwrap().to_string(),
+      "error: expected `)`, found `;`
+ --> lib.dsl:2:2
+  |
+2 | (;
+  |  ^ expected `)`"
+    );
+  }
+}
@@ -0,0 +1,188 @@
+use crate::{
+  error::{Expected, ParseError},
+  input::{Token, TokenBased},
+  Parser, TopParser,
+};
+
+/// Top parser yielding a token of the input.
+type TokenParser<'a, F, I> = TopParser<'a, F, &'a <I as TokenBased<'a>>::Token, I>;
+
+/// Take the next token if it satisfies a predicate.
+fn take_if<'a, I>(
+  input: I,
+  predicate: impl FnOnce(&I::Token) -> bool,
+  expected: impl FnOnce() -> Expected,
+) -> Parser<&'a I::Token, I>
+where
+  I: TokenBased<'a>,
+{
+  match input.as_tokens().first() {
+    Some(token) if predicate(token) => Parser::Parsed {
+      data: token,
+      input: input.advance(1),
+    },
+
+    Some(_) => Parser::NoParse {
+      error: ParseError::new(input, expected()),
+    },
+
+    None => Parser::end_of_input(input, expected(), 1),
+  }
+}
+
+/// Parse a token of a given kind.
+pub fn parse_token<'a, I>(
+  kind: <I::Token as Token>::Kind,
+) -> TokenParser<'a, impl Fn(I) -> Parser<&'a I::Token, I>, I>
+where
+  I: 'a + TokenBased<'a>,
+{
+  TopParser::from_input_parser(move |input: I| {
+    take_if(
+      input,
+      |token| *token.kind() == kind,
+      || Expected::named(format!("{:?}", kind)),
+    )
+  })
+}
+
+/// Parse a token satisfying a predicate.
+pub fn parse_satisfy<'a, I>(
+  predicate: impl 'a + Fn(&I::Token) -> bool,
+) -> TokenParser<'a, impl Fn(I) -> Parser<&'a I::Token, I>, I>
+where
+  I: 'a + TokenBased<'a>,
+{
+  TopParser::from_input_parser(move |input: I| {
+    take_if(input, &predicate, || Expected::named("matching token"))
+  })
+}
+
+/// Parse any token.
+pub fn parse_any_token<'a, I>() -> TokenParser<'a, impl Fn(I) -> Parser<&'a I::Token, I>, I>
+where
+  I: 'a + TokenBased<'a>,
+{
+  TopParser::from_input_parser(|input: I| take_if(input, |_| true, || Expected::named("token")))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    input::{Position, Span, Tokens},
+    Parse, ParseExt,
+  };
+
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  enum Kind {
+    Ident,
+    Num,
+    Plus,
+    LParen,
+    RParen,
+  }
+
+  type Tok = (Kind, Span, &'static str);
+
+  /// Tokens of a single line, separated by spaces.
+  fn lex(source: &'static str) -> Vec<Tok> {
+    let mut offset = 0;
+
+    source
+      .split(' ')
+      .map(|text| {
+        let kind = match text {
+          "+" => Kind::Plus,
+          "(" => Kind::LParen,
+          ")" => Kind::RParen,
+          _ if text.bytes().all(|b| b.is_ascii_digit()) => Kind::Num,
+          _ => Kind::Ident,
+        };
+        let pos = |offset| Position {
+          offset,
+          line: 0,
+          col: offset,
+        };
+        let span = Span::new(pos(offset), pos(offset + text.len()));
+        offset += text.len() + 1;
+
+        (kind, span, text)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn parse_token_test() {
+    let (tokens, missing, unclosed) = (lex("( a + 12 )"), lex("( a + )"), lex("( a"));
+    let atom = parse_token(Kind::Ident).or(parse_token(Kind::Num));
+    let parser = parse_token(Kind::LParen)
+      .right(atom.delimited1(parse_token(Kind::Plus)))
+      .left(parse_token(Kind::RParen));
+
+    let atoms = parser.parse(Tokens::new(&tokens)).ok().unwrap();
+    assert_eq!(
+      atoms.iter().map(|t| t.2).collect::<Vec<_>>(),
+      vec!["a", "12"]
+    );
+
+    let error = parser.parse(Tokens::new(&missing)).result().unwrap_err();
+
+    assert_eq!(error.to_string(), "expected one of: Ident, Num; found `)`");
+    assert_eq!(error.position().col, 6);
+
+    let error = parser.parse(Tokens::new(&unclosed)).result().unwrap_err();
+
+    assert_eq!(error.to_string(), "expected RParen, found end of input");
+    assert_eq!(error.position().col, 3);
+  }
+
+  #[test]
+  fn parse_satisfy_test() {
+    let tokens = lex("foo bar 1");
+    let long_ident = parse_satisfy(|t: &Tok| t.0 == Kind::Ident && t.2.len() > 2);
+
+    assert_eq!(
+      long_ident
+        .many0()
+        .right(parse_any_token())
+        .parse(Tokens::new(&tokens))
+        .ok()
+        .map(|t| t.2),
+      Some("1")
+    );
+    assert_eq!(
+      parse_any_token()
+        .parse(Tokens::new(&tokens[..0]))
+        .result()
+        .unwrap_err()
+        .to_string(),
+      "expected token, found end of input"
+    );
+  }
+
+  #[test]
+  fn token_spanned_test() {
+    let tokens = lex("f ( a + b ) c");
+    let parser = parse_token(Kind::Ident).right(
+      parse_token(Kind::LParen)
+        .right(parse_satisfy(|t: &Tok| t.0 != Kind::RParen).many0())
+        .spanned(),
+    );
+
+    let span = parser.parse(Tokens::new(&tokens)).ok().unwrap().span;
+
+    // from the start of the first token to the end of the last one, leaving out the space after it
+    assert_eq!((span.start.offset, span.end.offset), (2, 9));
+
+    let span = parse_any_token()
+      .right(parse_token(Kind::Num).opt().spanned())
+      .parse(Tokens::new(&tokens))
+      .ok()
+      .unwrap()
+      .span;
+
+    assert!(span.is_empty());
+    assert_eq!(span.start.offset, 2);
+  }
+}